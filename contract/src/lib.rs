@@ -10,7 +10,9 @@ extern crate pwasm_abi_derive;
 
 use tiny_keccak::Keccak;
 use pwasm_ethereum as eth;
+use pwasm_std::Vec;
 use pwasm_abi::types::*;
+use pwasm_abi::eth::EndpointInterface;
 use pwasm_abi_derive::eth_abi;
 
 // `TokenContract` is an interface definition of a contract.
@@ -56,17 +58,377 @@ pub trait TokenContract {
 	/// these standardized APIs for approval:
 	fn transferFrom(&mut self, _from: Address, _to: Address, _amount: U256) -> bool;
 
+	/// Send _amount from _from to _to and a _fee from _from to _fee_to, for flows where
+	/// a protocol fee is paid on the owner's behalf. The allowance must cover _amount plus _fee.
+	/// Neither _to nor _fee_to can be _from.
+	fn transferFromWithFee(&mut self, _from: Address, _to: Address, _amount: U256, _fee: U256, _fee_to: Address) -> bool;
+
 	/// Allow _spender to withdraw from your account, multiple times, up to the _value amount.
 	/// If this function is called again it overwrites the current allowance with _value.
+	/// Approving yourself fails.
 	fn approve(&mut self, _spender: Address, _value: U256) -> bool;
 
-	/// Check the amount of tokens spender have right to spend on behalf of owner
+	/// Check the amount of tokens spender have right to spend on behalf of owner.
+	/// This is the approved value (with decay applied) for ERC20 compatibility;
+	/// see `effectiveAllowance` for what the spender can actually move right now.
 	fn allowance(&mut self, _owner: Address, _spender: Address) -> U256;
 
+	/// Allow _spender to withdraw up to _cap_per_epoch from your account within every
+	/// period of _epoch_seconds. The spent amount resets when a new epoch starts.
+	/// Calling `approve` afterwards turns the allowance back into a static one.
+	fn approvePeriodic(&mut self, _spender: Address, _cap_per_epoch: U256, _epoch_seconds: U256) -> bool;
+
+	/// Is the balance slot of _account nonzero? Useful for gas estimation,
+	/// since writing to an empty slot costs more than updating an occupied one.
+	#[constant]
+	fn isSlotOccupied(&mut self, _account: Address) -> bool;
+
+	/// Every account which held a nonzero balance and wasn't pruned since, in order of first receipt
+	/// except where pruning moved the last account into a gap.
+	/// Storage isn't enumerable, so this is what a migration snapshot is built from.
+	#[constant]
+	fn allHolders(&mut self) -> Vec<Address>;
+
+	/// Burn _amounts[i] from _accounts[i], capped at each account's balance. Only the owner may call it.
+	/// Fails without burning anything if the lengths of the lists differ, they exceed `maxBatchSize`
+	/// or the batch would take the total supply below the supply floor.
+	fn burnBatch(&mut self, _accounts: Vec<Address>, _amounts: Vec<U256>) -> bool;
+
+	/// Total amount of tokens burned since the contract was created
+	#[constant]
+	fn totalBurned(&mut self) -> U256;
+
+	/// Do the balances of all holders plus the burned tokens add up to the supply
+	/// the contract was created with, changed by the owner's balance adjustments?
+	#[constant]
+	fn reconcile(&mut self) -> bool;
+
+	/// Require spenders to accept an allowance (see `acceptAllowance`) before `transferFrom` honors it.
+	/// Guards against approving contracts which can't handle the token. Only the owner may call it.
+	fn setAllowanceAcceptance(&mut self, _required: bool) -> bool;
+
+	/// Do spenders have to accept allowances before using them?
+	#[constant]
+	fn allowanceAcceptanceRequired(&mut self) -> bool;
+
+	/// Accept the allowance _owner approved for the sender. A new approval has to be accepted again.
+	fn acceptAllowance(&mut self, _owner: Address) -> bool;
+
+	/// State of the allowance of _spender: 0 none, 1 proposed (waiting for acceptance), 2 active
+	#[constant]
+	fn allowanceStatus(&mut self, _owner: Address, _spender: Address) -> u8;
+
+	/// Move _amount from _from to _to for internal bookkeeping, without a `Transfer` event.
+	/// Only the owner may call it. Indexers and wallets following `Transfer` events won't see
+	/// these moves, which breaks standard ERC20 event auditing, so use it knowingly.
+	fn internalTransfer(&mut self, _from: Address, _to: Address, _amount: U256) -> bool;
+
+	/// Number of outgoing transfers made from _account
+	#[constant]
+	fn transferCount(&mut self, _account: Address) -> U256;
+
+	/// Block or unblock transfers to _recipient, e.g. a known scam contract. Only the owner may call it.
+	/// Unlike freezing, the recipient itself can still send its tokens.
+	fn blockRecipient(&mut self, _recipient: Address, _blocked: bool) -> bool;
+
+	/// Are transfers to _recipient blocked?
+	#[constant]
+	fn isRecipientBlocked(&mut self, _recipient: Address) -> bool;
+
+	/// The total supply the contract was constructed with, its only constructor argument
+	#[constant]
+	fn initParams(&mut self) -> U256;
+
+	/// Allow _spender to withdraw up to _total from your account, but no more than _per_tx
+	/// in a single `transferFrom`. Calling `approve` afterwards removes the per-transaction limit.
+	fn approveWithTxLimit(&mut self, _spender: Address, _total: U256, _per_tx: U256) -> bool;
+
+	/// The most _spender may move in a single `transferFrom`, zero if there is no per-transaction limit
+	#[constant]
+	fn txLimit(&mut self, _owner: Address, _spender: Address) -> U256;
+
+	/// May _account perform the privileged _action? Actions are: 0 burn (`burnBatch`),
+	/// 1 `internalTransfer`, 2 `blockRecipient`, 3 configure (the `set*` settings),
+	/// 4 `distributeTreasury`, 5 `depositReward`, 6 `renounceOwnership`, 7 `adminAdjustBalance`,
+	/// 8 `emergencyEvacuate`.
+	/// Unknown actions are never authorized.
+	#[constant]
+	fn isAuthorizedFor(&mut self, _account: Address, _action: u8) -> bool;
+
+	/// Transfer _amount split between two recipients: _secondary gets _secondary_bps
+	/// basis points of it (at most 10000) and _primary the remainder.
+	fn transferSplit(&mut self, _primary: Address, _secondary: Address, _amount: U256, _secondary_bps: U256) -> bool;
+
+	/// Allow _spender to withdraw up to _value from your account, reduced by
+	/// _decay_per_second for every second the allowance is left unused.
+	/// Calling `approve` afterwards turns the allowance back into a static one.
+	fn approveDecaying(&mut self, _spender: Address, _value: U256, _decay_per_second: U256) -> bool;
+
+	/// The amount _spender can actually move from _owner in a single `transferFrom` right now,
+	/// taking acceptance, periodic caps, decay and per-transaction limits into account
+	#[constant]
+	fn effectiveAllowance(&mut self, _owner: Address, _spender: Address) -> U256;
+
+	/// Distribute the contract's own token balance to _recipients in proportion to _shares.
+	/// Rounding dust goes to the last recipient. Fails if any recipient couldn't receive its payout
+	/// with a transfer. Only the owner may call it.
+	fn distributeTreasury(&mut self, _recipients: Vec<Address>, _shares: Vec<U256>) -> bool;
+
+	/// Move all of the contract's ether and its own token balance to _to in an emergency.
+	/// Undistributed rewards stay, they belong to the holders. Fails if _to couldn't receive the tokens
+	/// with a transfer. Only the owner may call it.
+	fn emergencyEvacuate(&mut self, _to: Address) -> bool;
+
+	/// Limit how many outgoing transfers an account may make within a single block,
+	/// mitigating sandwiching. Zero removes the limit. Only the owner may call it.
+	fn setBlockTransferLimit(&mut self, _limit: U256) -> bool;
+
+	/// Number of outgoing transfers _account made in the current block.
+	/// Transfers are only counted while a per-block limit is set.
+	#[constant]
+	fn transfersThisBlock(&mut self, _account: Address) -> u32;
+
+	/// Require received tokens to be held for _seconds before they can be transferred out.
+	/// Zero removes the holding period. Only the owner may call it.
+	fn setHoldingPeriod(&mut self, _seconds: U256) -> bool;
+
+	/// Time at which the first tokens _account holds back become transferable, zero if none are held back.
+	/// Later receipts never delay it.
+	#[constant]
+	fn unlockTime(&mut self, _account: Address) -> U256;
+
+	/// Has the constructor run? Lets deployers check initialization without triggering it.
+	#[constant]
+	fn isInitialized(&mut self) -> bool;
+
+	/// The most _spender could pull from _owner with `transferFrom` right now:
+	/// the smaller of the owner's transferable balance and the spender's effective allowance
+	#[constant]
+	fn spendCapacity(&mut self, _owner: Address, _spender: Address) -> U256;
+
+	/// The contract owner
+	#[constant]
+	fn owner(&mut self) -> Address;
+
+	/// Does the owner hold exactly _expected tokens? A cheap spot-check for monitoring scripts.
+	#[constant]
+	fn verifyOwnerBalance(&mut self, _expected: U256) -> bool;
+
+	/// Deposit _amount of the owner's tokens as rewards for all holders, shared in proportion
+	/// to their balances at the time of the deposit. Only the owner may call it.
+	fn depositReward(&mut self, _amount: U256) -> bool;
+
+	/// Pay the sender the rewards it earned, returns the amount paid
+	fn claimReward(&mut self) -> U256;
+
+	/// Rewards _account earned and hasn't claimed yet
+	#[constant]
+	fn earned(&mut self, _account: Address) -> U256;
+
+	/// Reject nonzero approvals below _min, preventing dust approvals.
+	/// Setting an allowance to zero is always possible. Only the owner may call it.
+	fn setMinApproval(&mut self, _min: U256) -> bool;
+
+	/// The smallest nonzero allowance that can be approved
+	#[constant]
+	fn minApproval(&mut self) -> U256;
+
+	/// Permanently remove the owner, setting it to the zero address and making every privileged
+	/// function unusable, whoever calls it. This is irreversible. Only the owner may call it.
+	fn renounceOwnership(&mut self) -> bool;
+
+	/// Only allow transfers of multiples of _unit, e.g. whole tokens, including each share of a split
+	/// and each fee. A unit of 0 or 1 allows any amount.
+	/// Only the owner may call it.
+	fn setTransferUnit(&mut self, _unit: U256) -> bool;
+
+	/// The unit every transferred amount has to be a multiple of
+	#[constant]
+	fn transferUnit(&mut self) -> U256;
+
+	/// The raw 32 bytes stored under _key, a debug view for auditors reconciling the contract's state.
+	/// Anyone may call it, contract storage is public on chain anyway.
+	#[constant]
+	fn rawStorage(&mut self, _key: H256) -> H256;
+
+	/// _account's position in one call: its balance, the part of it still held back by the
+	/// holding period or a lock and the rewards it can claim
+	#[constant]
+	fn positionOf(&mut self, _account: Address) -> (U256, U256, U256);
+
+	/// Allow _spender a single `transferFrom` of up to _value from your account.
+	/// The allowance is revoked entirely by the first spend, even if only part of it was used.
+	fn approveOnce(&mut self, _spender: Address, _value: U256) -> bool;
+
+	/// Is the allowance of _spender single-use?
+	#[constant]
+	fn isOneShot(&mut self, _owner: Address, _spender: Address) -> bool;
+
+	/// Stop or resume receiving tokens, e.g. to avoid spam airdrops. Receiving is enabled by default.
+	fn setReceiveEnabled(&mut self, _enabled: bool);
+
+	/// Does _account accept incoming transfers?
+	#[constant]
+	fn receiveEnabled(&mut self, _account: Address) -> bool;
+
+	/// Move _amounts[i] from _froms[i] to _to for every i, using the sender's allowances.
+	/// Rejects the whole batch if any entry would fail as a `transferFrom`, or an owner is listed twice.
+	fn transferFromBatch(&mut self, _froms: Vec<Address>, _to: Address, _amounts: Vec<U256>) -> bool;
+
+	/// Is the allowance slot of _spender nonzero? Clearing an occupied slot earns a storage refund,
+	/// which helps estimating the cost of revoking.
+	#[constant]
+	fn allowanceOccupied(&mut self, _owner: Address, _spender: Address) -> bool;
+
+	/// Never let burning take the total supply below _floor. Only the owner may call it.
+	fn setSupplyFloor(&mut self, _floor: U256) -> bool;
+
+	/// The total supply burning can't go below
+	#[constant]
+	fn supplyFloor(&mut self) -> U256;
+
+	/// Remove _account from `allHolders` if its balance is zero. Anyone may call it.
+	fn pruneHolder(&mut self, _account: Address) -> bool;
+
+	/// Exempt _account from the per-block transfer limit, e.g. a market maker.
+	/// Only the owner may call it.
+	fn setBlockLimitExempt(&mut self, _account: Address, _exempt: bool) -> bool;
+
+	/// Is _account exempt from the per-block transfer limit?
+	#[constant]
+	fn isBlockLimitExempt(&mut self, _account: Address) -> bool;
+
+	/// The selectors of `balanceOf`, `totalSupply`, `transfer`, `transferFrom`, `approve`
+	/// and `allowance`, for tooling verifying the contract implements ERC20
+	#[constant]
+	fn erc20Selectors(&mut self) -> Vec<u32>;
+
+	/// Every owner who ever approved _spender a nonzero allowance, in order of their first approval
+	#[constant]
+	fn ownersApproving(&mut self, _spender: Address) -> Vec<Address>;
+
+	/// The allowances of _spender, in the order of `ownersApproving`
+	#[constant]
+	fn allowancesOfSpender(&mut self, _spender: Address) -> Vec<U256>;
+
+	/// Allow _spender to withdraw up to _value from your account in at most _max_uses `transferFrom` calls.
+	/// The allowance is revoked entirely by the last use, even if only part of it was spent.
+	fn approveWithUses(&mut self, _spender: Address, _value: U256, _max_uses: u32) -> bool;
+
+	/// How many more times _spender may use its allowance, zero if the number of uses isn't limited
+	#[constant]
+	fn remainingUses(&mut self, _owner: Address, _spender: Address) -> u32;
+
+	/// Every owner setting in one call: (allowanceAcceptanceRequired, blockTransferLimit,
+	/// holdingPeriod, minApproval, transferUnit, supplyFloor, maxAllowancePerSpender, selfTransferMode)
+	#[constant]
+	fn config(&mut self) -> (bool, U256, U256, U256, U256, U256, U256, u8);
+
+	/// Run several ABI-encoded calls of this contract's methods in order, as the sender, e.g. an approval
+	/// and a `transferFrom`. Only state-changing methods with fixed-size arguments can be called, so
+	/// not the batch methods or another `multicall`. A call counts as successful if it returned a nonzero
+	/// first word, i.e. `true` for the boolean methods and a reward for `claimReward`, or nothing,
+	/// as `setReceiveEnabled` does. Unsupported calls, calls with the wrong length and calls with
+	/// invalid arguments, e.g. a `bool` other than 0 or 1, fail.
+	fn multicall(&mut self, _calls: Vec<Vec<u8>>) -> Vec<bool>;
+
+	/// Never let the balance of _account drop below _min by its own transfers, e.g. a validator's stake.
+	/// Only the owner may call it.
+	fn setMinRetained(&mut self, _account: Address, _min: U256) -> bool;
+
+	/// The balance _account has to keep
+	#[constant]
+	fn minRetained(&mut self, _account: Address) -> U256;
+
+	/// Reject approvals above _max, limiting what a single spender can be allowed.
+	/// Zero removes the limit. Only the owner may call it.
+	fn setMaxAllowancePerSpender(&mut self, _max: U256) -> bool;
+
+	/// The largest allowance that can be approved, zero if there is no limit
+	#[constant]
+	fn maxAllowancePerSpender(&mut self) -> U256;
+
+	/// Number of spenders _owner has a nonzero allowance stored for
+	#[constant]
+	fn allowanceCountOf(&mut self, _owner: Address) -> u32;
+
+	/// Transfer _amount to _to, locked there until _release_time, e.g. a vesting grant.
+	/// Locking again before the release only adds to the locked amount with the same _release_time,
+	/// other release times fail, so nobody can delay the release of a grant.
+	/// The locked tokens aren't held for the holding period on top.
+	/// The contract itself can't be the recipient.
+	fn transferAndLock(&mut self, _to: Address, _amount: U256, _release_time: U256) -> bool;
+
+	/// The part of _account's balance it can transfer now, without held or locked tokens
+	#[constant]
+	fn availableBalance(&mut self, _account: Address) -> U256;
+
+	/// Has _account ever received tokens, including the initial supply?
+	/// Every account has to receive tokens before it can send any.
+	#[constant]
+	fn hasTransacted(&mut self, _account: Address) -> bool;
+
+	/// Most entries `burnBatch`, `distributeTreasury`, `transferFromBatch` and `multicall` accept.
+	/// Longer batches are rejected before doing any work.
+	#[constant]
+	fn maxBatchSize(&mut self) -> u32;
+
+	/// Sum of the balances of all holders, saturating instead of overflowing.
+	/// Auditors can compare it against `totalSupply`.
+	#[constant]
+	fn sumHolderBalances(&mut self) -> U256;
+
+	/// Transfer _amount to _to, attaching a payment reference. The memo is only emitted with
+	/// a `TransferMemo` event following the `Transfer` event, it isn't stored.
+	fn transferWithMemo(&mut self, _to: Address, _amount: U256, _memo: H256) -> bool;
+
+	/// Choose what transfers do with tokens sent to the contract itself, usually a mistake:
+	/// 0 keeps them in the treasury, 1 rejects the transfer, 2 burns them.
+	/// Only the owner may call it.
+	fn setSelfTransferMode(&mut self, _mode: u8) -> bool;
+
+	/// What happens to tokens sent to the contract itself, see `setSelfTransferMode`
+	#[constant]
+	fn selfTransferMode(&mut self) -> u8;
+
+	/// The most _account could send in a single transfer right now: its balance without held or
+	/// locked tokens and its retained minimum, rounded down to the transfer unit. Zero once the
+	/// account made as many transfers in this block as the per-block limit allows.
+	#[constant]
+	fn maxTransferableNow(&mut self, _account: Address) -> U256;
+
+	/// Set the balance of _account to _new_balance, changing the total supply by the difference,
+	/// e.g. to correct the effects of a bug. Emits `AdminAdjustment` rather than `Transfer`.
+	/// Adjustments aren't burns, `reconcile` accounts for them separately. Lowering a balance can't take
+	/// the total supply below the floor, and the contract's own balance can't be adjusted.
+	/// Only the owner may call it.
+	fn adminAdjustBalance(&mut self, _account: Address, _new_balance: U256) -> bool;
+
 	#[event]
 	fn Transfer(&mut self, indexed_from: Address, indexed_to: Address, _value: U256);
 	#[event]
 	fn Approval(&mut self, indexed_owner: Address, indexed_spender: Address, _value: U256);
+	#[event]
+	fn OwnershipTransferred(&mut self, indexed_previous_owner: Address, indexed_new_owner: Address);
+	#[event]
+	fn TransferMemo(&mut self, indexed_from: Address, indexed_to: Address, _memo: H256);
+	#[event]
+	fn AdminAdjustment(&mut self, indexed_account: Address, _old_balance: U256, _new_balance: U256);
+}
+
+// Reads a storage slot.
+// All storage access goes through storage_read and storage_write, so tests can count it.
+fn storage_read(key: &H256) -> [u8; 32] {
+	#[cfg(test)]
+	storage_counter::count_read();
+	eth::read(key)
+}
+
+// Writes a storage slot
+fn storage_write(key: &H256, value: &[u8; 32]) {
+	#[cfg(test)]
+	storage_counter::count_write();
+	eth::write(key, value)
 }
 
 fn total_supply_key() -> H256 {
@@ -77,34 +439,333 @@ fn owner_key() -> H256 {
 	H256::from([3,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
 }
 
+fn holders_count_key() -> H256 {
+	H256::from([4,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn original_supply_key() -> H256 {
+	H256::from([5,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn total_burned_key() -> H256 {
+	H256::from([6,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn allowance_acceptance_key() -> H256 {
+	H256::from([7,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn block_transfer_limit_key() -> H256 {
+	H256::from([8,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn holding_period_key() -> H256 {
+	H256::from([9,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn initialized_key() -> H256 {
+	H256::from([10,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn reward_per_token_key() -> H256 {
+	H256::from([11,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn reward_pool_key() -> H256 {
+	H256::from([12,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn min_approval_key() -> H256 {
+	H256::from([13,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn transfer_unit_key() -> H256 {
+	H256::from([14,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn supply_floor_key() -> H256 {
+	H256::from([15,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn multicall_lock_key() -> H256 {
+	H256::from([16,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn max_allowance_key() -> H256 {
+	H256::from([17,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn self_transfer_mode_key() -> H256 {
+	H256::from([18,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn adjusted_up_key() -> H256 {
+	H256::from([19,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+fn adjusted_down_key() -> H256 {
+	H256::from([20,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0])
+}
+
+// Reads the contract owner
+fn read_owner() -> Address {
+	Address::from(H256::from(storage_read(&owner_key())))
+}
+
+// Most entries a batch method accepts, keeping a batch well within the block gas limit
+const MAX_BATCH_SIZE: usize = 100;
+
+// Privileged actions, see `isAuthorizedFor`
+const ACTION_BURN: u8 = 0;
+const ACTION_INTERNAL_TRANSFER: u8 = 1;
+const ACTION_BLOCK_RECIPIENT: u8 = 2;
+const ACTION_CONFIGURE: u8 = 3;
+const ACTION_DISTRIBUTE_TREASURY: u8 = 4;
+const ACTION_DEPOSIT_REWARD: u8 = 5;
+const ACTION_RENOUNCE_OWNERSHIP: u8 = 6;
+const ACTION_ADJUST_BALANCE: u8 = 7;
+const ACTION_EMERGENCY_EVACUATE: u8 = 8;
+
+// May the account perform the privileged action?
+// Every privileged method checks its caller here. Nobody may once the ownership is renounced.
+fn is_authorized(account: &Address, action: u8) -> bool {
+	match action {
+		ACTION_BURN | ACTION_INTERNAL_TRANSFER | ACTION_BLOCK_RECIPIENT |
+		ACTION_CONFIGURE | ACTION_DISTRIBUTE_TREASURY | ACTION_DEPOSIT_REWARD |
+		ACTION_RENOUNCE_OWNERSHIP | ACTION_ADJUST_BALANCE | ACTION_EMERGENCY_EVACUATE => {
+			let owner = read_owner();
+			owner != Address::zero() && *account == owner
+		},
+		_ => false,
+	}
+}
+
 // Reads balance by address
 fn read_balance_of(owner: &Address) -> U256 {
-	U256::from_big_endian(&eth::read(&balance_key(owner)))
+	U256::from_big_endian(&storage_read(&balance_key(owner)))
 }
 
 // Reads allowance value using key
 // Key generated by allowance_key function
 fn read_allowance(key: &H256) -> U256 {
-	U256::from_big_endian(&eth::read(key))
+	U256::from_big_endian(&storage_read(key))
+}
+
+// Reads a U256 value stored under the key
+fn read_u256(key: &H256) -> U256 {
+	U256::from_big_endian(&storage_read(key))
 }
 
 // Writes allowance value
 // Key generated by allowance_key function
 fn write_allowance(key: &H256, value: U256) {
-	eth::write(key, &value.into())
+	storage_write(key, &value.into())
 }
 
-// Generates the "allowance" storage key to map owner and spender
-fn allowance_key(owner: &Address, spender: &Address) -> H256 {
+// Keeps count of the owner's nonzero allowances when one of them changes
+fn count_allowance_change(owner: &Address, before: U256, after: U256) {
+	if before.is_zero() == after.is_zero() {
+		return;
+	}
+	let key = account_key("allowance_count_key", owner);
+	let count = read_u256(&key);
+	storage_write(&key, &(if after.is_zero() { count - 1 } else { count + 1 }).into());
+}
+
+// Writes the allowance of the spender, keeping count of the owner's nonzero allowances
+fn set_allowance(owner: &Address, spender: &Address, value: U256) {
+	let key = allowance_key(owner, spender);
+	let before = read_allowance(&key);
+	write_allowance(&key, value);
+	count_allowance_change(owner, before, value);
+}
+
+// Generates a storage key in the given namespace to map owner and spender
+fn pair_key(namespace: &str, owner: &Address, spender: &Address) -> H256 {
 	let mut keccak = Keccak::new_keccak256();
 	let mut res = H256::zero();
-	keccak.update("allowance_key".as_ref());
+	keccak.update(namespace.as_ref());
 	keccak.update(owner.as_ref());
 	keccak.update(spender.as_ref());
 	keccak.finalize(&mut res[..]);
 	res
 }
 
+// Generates a storage key in the given namespace for an account
+fn account_key(namespace: &str, account: &Address) -> H256 {
+	let mut keccak = Keccak::new_keccak256();
+	let mut res = H256::zero();
+	keccak.update(namespace.as_ref());
+	keccak.update(account.as_ref());
+	keccak.finalize(&mut res[..]);
+	res
+}
+
+// Generates the key of an entry of the list of owners who approved the spender
+fn approving_owner_at_key(spender: &Address, index: U256) -> H256 {
+	let mut keccak = Keccak::new_keccak256();
+	let mut res = H256::zero();
+	let index: [u8; 32] = index.into();
+	keccak.update("approving_owner_at_key".as_ref());
+	keccak.update(spender.as_ref());
+	keccak.update(&index);
+	keccak.finalize(&mut res[..]);
+	res
+}
+
+// Appends the owner to the list of owners who approved the spender, on its first nonzero approval.
+// As with holders, the position in the list (plus one) is kept per pair, so every owner is listed once.
+fn add_approving_owner(owner: &Address, spender: &Address, value: U256) {
+	let position_key = pair_key("approving_owner_position_key", owner, spender);
+	if value.is_zero() || !read_u256(&position_key).is_zero() {
+		return;
+	}
+	let count_key = account_key("approving_owners_count_key", spender);
+	let count = read_u256(&count_key);
+	storage_write(&approving_owner_at_key(spender, count), &H256::from(*owner).into());
+	storage_write(&count_key, &(count + 1).into());
+	storage_write(&position_key, &(count + 1).into());
+}
+
+// Reads the list of owners who approved the spender
+fn read_approving_owners(spender: &Address) -> Vec<Address> {
+	let count = read_u256(&account_key("approving_owners_count_key", spender)).low_u64();
+	(0..count)
+		.map(|index| Address::from(H256::from(storage_read(&approving_owner_at_key(spender, index.into())))))
+		.collect()
+}
+
+// Generates the key of an entry of the holders list
+fn holder_at_key(index: U256) -> H256 {
+	let mut keccak = Keccak::new_keccak256();
+	let mut res = H256::zero();
+	let index: [u8; 32] = index.into();
+	keccak.update("holder_at_key".as_ref());
+	keccak.update(&index);
+	keccak.finalize(&mut res[..]);
+	res
+}
+
+// Appends an address to the list of accounts which held tokens.
+// The position in the list (plus one) is kept per account, so every address is listed once.
+// Also marks the account as having transacted, which pruning doesn't undo.
+fn add_holder(address: &Address) {
+	let position_key = account_key("holder_position_key", address);
+	if !read_u256(&position_key).is_zero() {
+		return;
+	}
+	let count = read_u256(&holders_count_key());
+	storage_write(&holder_at_key(count), &H256::from(*address).into());
+	storage_write(&holders_count_key(), &(count + 1).into());
+	storage_write(&position_key, &(count + 1).into());
+	storage_write(&account_key("transacted_key", address), &U256::one().into());
+}
+
+// Removes an address from the list of accounts which held tokens,
+// moving the last entry into its place
+fn remove_holder(address: &Address) {
+	let position_key = account_key("holder_position_key", address);
+	let position = read_u256(&position_key);
+	if position.is_zero() {
+		return;
+	}
+	let last_index = read_u256(&holders_count_key()) - 1;
+	if position - 1 != last_index {
+		let last = storage_read(&holder_at_key(last_index));
+		storage_write(&holder_at_key(position - 1), &last);
+		storage_write(&account_key("holder_position_key", &Address::from(H256::from(last))), &position.into());
+	}
+	storage_write(&holder_at_key(last_index), &[0u8; 32]);
+	storage_write(&holders_count_key(), &last_index.into());
+	storage_write(&position_key, &[0u8; 32]);
+}
+
+// Reads the list of accounts which held tokens
+fn read_holders() -> Vec<Address> {
+	let count = read_u256(&holders_count_key()).low_u64();
+	(0..count)
+		.map(|index| Address::from(H256::from(storage_read(&holder_at_key(index.into())))))
+		.collect()
+}
+
+// Generates the "allowance" storage key to map owner and spender
+fn allowance_key(owner: &Address, spender: &Address) -> H256 {
+	pair_key("allowance_key", owner, spender)
+}
+
+// Flags of an allowance telling which optional rules apply to it.
+// `approve` clears them, so every approval starts as a plain allowance.
+const PERIODIC_ALLOWANCE: u64 = 1;
+const ACCEPTED_ALLOWANCE: u64 = 2;
+const TX_LIMITED_ALLOWANCE: u64 = 4;
+const DECAYING_ALLOWANCE: u64 = 8;
+const ONE_SHOT_ALLOWANCE: u64 = 16;
+const LIMITED_USES_ALLOWANCE: u64 = 32;
+
+fn read_allowance_flags(owner: &Address, spender: &Address) -> u64 {
+	read_u256(&pair_key("allowance_flags_key", owner, spender)).low_u64()
+}
+
+fn write_allowance_flags(owner: &Address, spender: &Address, flags: u64) {
+	storage_write(&pair_key("allowance_flags_key", owner, spender), &U256::from(flags).into())
+}
+
+// Do spenders have to accept an allowance before they can use it?
+fn allowance_acceptance_required() -> bool {
+	!read_u256(&allowance_acceptance_key()).is_zero()
+}
+
+// Reads an allowance which loses `decay_rate` tokens every second since `decay_start`
+fn read_decayed_allowance(owner: &Address, spender: &Address) -> U256 {
+	let value = read_allowance(&allowance_key(owner, spender));
+	let start = read_u256(&pair_key("decay_start_key", owner, spender));
+	let rate = read_u256(&pair_key("decay_rate_key", owner, spender));
+	let elapsed = U256::from(eth::timestamp()) - start;
+	match elapsed.checked_mul(rate) {
+		Some(decayed) if decayed < value => value - decayed,
+		_ => 0.into(),
+	}
+}
+
+// Allowance which refills to `cap` every `epoch` seconds
+struct PeriodicAllowance {
+	cap: U256,
+	epoch: U256,
+	epoch_start: U256,
+	spent: U256,
+}
+
+impl PeriodicAllowance {
+	fn remaining(&self) -> U256 {
+		if self.spent < self.cap { self.cap - self.spent } else { 0.into() }
+	}
+}
+
+// Reads the periodic allowance of a spender.
+// Rolls the epoch forward (resetting the spent amount) when the current one has elapsed.
+fn read_periodic_allowance(owner: &Address, spender: &Address) -> PeriodicAllowance {
+	let mut periodic = PeriodicAllowance {
+		cap: read_u256(&pair_key("periodic_cap_key", owner, spender)),
+		epoch: read_u256(&pair_key("periodic_epoch_key", owner, spender)),
+		epoch_start: read_u256(&pair_key("periodic_start_key", owner, spender)),
+		spent: read_u256(&pair_key("periodic_spent_key", owner, spender)),
+	};
+	let elapsed = U256::from(eth::timestamp()) - periodic.epoch_start;
+	if elapsed >= periodic.epoch {
+		periodic.epoch_start = periodic.epoch_start + elapsed - elapsed % periodic.epoch;
+		periodic.spent = 0.into();
+	}
+	periodic
+}
+
+// Writes the periodic allowance of a spender
+fn write_periodic_allowance(owner: &Address, spender: &Address, periodic: &PeriodicAllowance) {
+	storage_write(&pair_key("periodic_cap_key", owner, spender), &periodic.cap.into());
+	storage_write(&pair_key("periodic_epoch_key", owner, spender), &periodic.epoch.into());
+	storage_write(&pair_key("periodic_start_key", owner, spender), &periodic.epoch_start.into());
+	storage_write(&pair_key("periodic_spent_key", owner, spender), &periodic.spent.into());
+}
+
 // Generates a balance key for some address.
 // Used to map balances with their owners.
 fn balance_key(address: &Address) -> H256 {
@@ -113,239 +774,3317 @@ fn balance_key(address: &Address) -> H256 {
 	key
 }
 
-pub struct TokenContractInstance;
+// Address of the ecrecover precompiled contract
+#[cfg(any(test, feature = "std"))]
+fn ecrecover_address() -> Address {
+	Address::from([0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1])
+}
+
+// Recovers the address which signed the digest using the ecrecover precompile.
+// Returns None for an invalid signature. No method verifies signatures yet, so it's only
+// compiled for `recover` and the tests.
+#[cfg(any(test, feature = "std"))]
+fn recover_signer(digest: &H256, v: u8, r: &H256, s: &H256) -> Option<Address> {
+	if v != 27 && v != 28 {
+		return None;
+	}
+	let mut input = [0u8; 128];
+	input[0..32].copy_from_slice(digest.as_ref());
+	input[63] = v;
+	input[64..96].copy_from_slice(r.as_ref());
+	input[96..128].copy_from_slice(s.as_ref());
+	let mut result = [0u8; 32];
+	if eth::call(3000, &ecrecover_address(), 0.into(), &input, &mut result).is_err() {
+		return None;
+	}
+	// The precompile returns nothing (zero address) when recovery fails
+	let signer = Address::from_slice(&result[12..]);
+	if signer.is_zero() { None } else { Some(signer) }
+}
+
+/// Recovers the signer of a digest, exposed for testing the signature based features.
+#[cfg(feature = "std")]
+pub fn recover(digest: &H256, v: u8, r: &H256, s: &H256) -> Option<Address> {
+	recover_signer(digest, v, r, s)
+}
+
+// May the owner approve the value for the spender?
+// Approving yourself is meaningless, nonzero approvals below the minimum are dust,
+// and none may exceed the maximum, if one is set.
+fn approval_permitted(owner: &Address, spender: &Address, value: U256) -> bool {
+	if owner == spender {
+		return false;
+	}
+	if value.is_zero() {
+		return true;
+	}
+	let max = read_u256(&max_allowance_key());
+	value >= read_u256(&min_approval_key()) && (max.is_zero() || value <= max)
+}
+
+// Amount of tokens the spender may currently transfer on behalf of the owner in a single call
+fn spendable_allowance(owner: &Address, spender: &Address) -> U256 {
+	let flags = read_allowance_flags(owner, spender);
+	if flags & ACCEPTED_ALLOWANCE == 0 && allowance_acceptance_required() {
+		return 0.into();
+	}
+	let allowed = if flags & PERIODIC_ALLOWANCE != 0 {
+		read_periodic_allowance(owner, spender).remaining()
+	} else if flags & DECAYING_ALLOWANCE != 0 {
+		read_decayed_allowance(owner, spender)
+	} else {
+		read_allowance(&allowance_key(owner, spender))
+	};
+	if flags & TX_LIMITED_ALLOWANCE != 0 {
+		let tx_limit = read_u256(&pair_key("tx_limit_key", owner, spender));
+		if tx_limit < allowed {
+			return tx_limit;
+		}
+	}
+	allowed
+}
+
+// Deducts a spent amount from the allowance.
+// The amount must not exceed spendable_allowance.
+fn spend_allowance(owner: &Address, spender: &Address, amount: U256) {
+	let flags = read_allowance_flags(owner, spender);
+	if flags & PERIODIC_ALLOWANCE != 0 {
+		let mut periodic = read_periodic_allowance(owner, spender);
+		periodic.spent = periodic.spent + amount;
+		write_periodic_allowance(owner, spender, &periodic);
+	} else if flags & DECAYING_ALLOWANCE != 0 {
+		// Restart the decay from what's left now
+		set_allowance(owner, spender, read_decayed_allowance(owner, spender) - amount);
+		storage_write(&pair_key("decay_start_key", owner, spender), &U256::from(eth::timestamp()).into());
+	} else if flags & ONE_SHOT_ALLOWANCE != 0 {
+		// Revoked entirely by the first spend, whatever is left
+		set_allowance(owner, spender, 0.into());
+	} else if flags & LIMITED_USES_ALLOWANCE != 0 {
+		let uses_key = pair_key("remaining_uses_key", owner, spender);
+		let uses = read_u256(&uses_key) - 1;
+		storage_write(&uses_key, &uses.into());
+		// Revoked entirely by the last use, whatever is left
+		let a_key = allowance_key(owner, spender);
+		let allowed = read_allowance(&a_key);
+		let left = if uses.is_zero() { 0.into() } else { allowed - amount };
+		write_allowance(&a_key, left);
+		count_allowance_change(owner, allowed, left);
+	} else {
+		let a_key = allowance_key(owner, spender);
+		let allowed = read_allowance(&a_key);
+		write_allowance(&a_key, allowed - amount);
+		count_allowance_change(owner, allowed, allowed - amount);
+	}
+}
+
+// Moves tokens between accounts, the sender must have enough of them
+fn move_tokens(from: &Address, to: &Address, amount: U256) {
+	settle_rewards(from);
+	settle_rewards(to);
+	let from_balance = read_balance_of(from);
+	storage_write(&balance_key(from), &(from_balance - amount).into());
+	let to_balance = read_balance_of(to);
+	storage_write(&balance_key(to), &(to_balance + amount).into());
+	#[cfg(test)]
+	assert_conserved(from, to, from_balance, to_balance);
+	add_holder(to);
+	hold_received(to, amount);
+}
+
+// Asserts that a transfer between the accounts didn't create or destroy tokens,
+// i.e. the debit equals the credit. Only compiled into test builds.
+// Reads storage directly, so the check doesn't show up in the storage counters.
+#[cfg(test)]
+fn assert_conserved(from: &Address, to: &Address, from_before: U256, to_before: U256) {
+	let balance = |address: &Address| U256::from_big_endian(&eth::read(&balance_key(address)));
+	let (before, after) = if from == to {
+		(from_before, balance(from))
+	} else {
+		(from_before + to_before, balance(from) + balance(to))
+	};
+	assert_eq!(before, after, "transfer didn't conserve tokens");
+}
+
+// Sums the balances of the distinct accounts. Only compiled into test builds.
+// Reads storage directly, so the check doesn't show up in the storage counters.
+#[cfg(test)]
+fn sum_balances(accounts: &[Address]) -> U256 {
+	accounts.iter().enumerate().filter(|&(i, account)| !accounts[..i].contains(account))
+		.fold(U256::zero(), |sum, (_, account)| sum + U256::from_big_endian(&eth::read(&balance_key(account))))
+}
+
+// Reads the total supply for `assert_supply_follows`, without counting the read
+#[cfg(test)]
+fn supply_unmetered() -> U256 {
+	U256::from_big_endian(&eth::read(&total_supply_key()))
+}
+
+// Asserts that changing the balances of the accounts changed the total supply by the same amount,
+// i.e. no tokens were burned or created without the supply. Only compiled into test builds.
+#[cfg(test)]
+fn assert_supply_follows(accounts: &[Address], balances_before: U256, supply_before: U256) {
+	assert_eq!(balances_before + supply_unmetered(), supply_before + sum_balances(accounts),
+		"total supply didn't follow the balances");
+}
+
+// Has the owner blocked transfers to the account?
+fn recipient_blocked(account: &Address) -> bool {
+	!read_u256(&account_key("blocked_recipient_key", account)).is_zero()
+}
+
+// Generates the key counting the transfers of an account within a block
+fn block_transfers_key(account: &Address, block: u64) -> H256 {
+	let mut keccak = Keccak::new_keccak256();
+	let mut res = H256::zero();
+	let block: [u8; 32] = U256::from(block).into();
+	keccak.update("block_transfers_key".as_ref());
+	keccak.update(account.as_ref());
+	keccak.update(&block);
+	keccak.finalize(&mut res[..]);
+	res
+}
+
+// Reads how many transfers the account made in the current block.
+// Transfers are only counted while a per-block limit is set.
+fn read_block_transfers(account: &Address) -> U256 {
+	read_u256(&block_transfers_key(account, eth::block_number()))
+}
+
+// Received tokens are held in two batches with their own receipt time: the first batch still held
+// and the latest receipts. Keys of the receipt time and amount of each batch, first batch first.
+const HOLD_KEYS: [&'static str; 4] = ["received_at_key", "received_held_key", "latest_received_at_key", "latest_held_key"];
+
+// Amount of the batch of the account's tokens which is still within the holding period
+fn held_batch(account: &Address, received_at_key: &str, held_key: &str, holding_period: U256) -> U256 {
+	let received_at = read_u256(&account_key(received_at_key, account));
+	if U256::from(eth::timestamp()) - received_at >= holding_period {
+		return 0.into();
+	}
+	read_u256(&account_key(held_key, account))
+}
+
+// Amounts of the first and the latest batch of the account's tokens still within the holding period
+fn held_batches(account: &Address) -> (U256, U256) {
+	let holding_period = read_u256(&holding_period_key());
+	if holding_period.is_zero() {
+		return (0.into(), 0.into());
+	}
+	(held_batch(account, HOLD_KEYS[0], HOLD_KEYS[1], holding_period),
+		held_batch(account, HOLD_KEYS[2], HOLD_KEYS[3], holding_period))
+}
+
+// Amount of the account's tokens which are still within the holding period
+fn held_tokens(account: &Address) -> U256 {
+	let (first, latest) = held_batches(account);
+	first + latest
+}
+
+// Records tokens received by the account, to be held for the holding period.
+// The first batch keeps its receipt time, so a receipt never delays it. While the first batch
+// is held, the latest one restarts with each receipt, which delays it by less than a period.
+// Once the first batch is released, the latest one takes its place with its receipt time.
+fn hold_received(account: &Address, amount: U256) {
+	if read_u256(&holding_period_key()).is_zero() {
+		return;
+	}
+	let (first, latest) = held_batches(account);
+	let (first_at, first, latest) = if first.is_zero() {
+		(read_u256(&account_key(HOLD_KEYS[2], account)), latest, amount)
+	} else {
+		(read_u256(&account_key(HOLD_KEYS[0], account)), first, latest + amount)
+	};
+	storage_write(&account_key(HOLD_KEYS[0], account), &first_at.into());
+	storage_write(&account_key(HOLD_KEYS[1], account), &first.into());
+	storage_write(&account_key(HOLD_KEYS[2], account), &U256::from(eth::timestamp()).into());
+	storage_write(&account_key(HOLD_KEYS[3], account), &latest.into());
+}
+
+// Amount of the account's tokens locked by `transferAndLock` which aren't released yet
+fn locked_tokens(account: &Address) -> U256 {
+	let locked = read_u256(&account_key("locked_amount_key", account));
+	if locked.is_zero() || U256::from(eth::timestamp()) >= read_u256(&account_key("locked_until_key", account)) {
+		return 0.into();
+	}
+	locked
+}
+
+// May tokens received by the account be locked until the release time? Tokens locked before
+// and not released yet keep their release time, so only a lock with the same one can be added.
+fn lock_permitted(account: &Address, release_time: U256) -> bool {
+	locked_tokens(account).is_zero() || read_u256(&account_key("locked_until_key", account)) == release_time
+}
+
+// Locks tokens received by the account until the release time, see `lock_permitted`
+fn lock_tokens(account: &Address, amount: U256, release_time: U256) {
+	let locked = locked_tokens(account);
+	storage_write(&account_key("locked_amount_key", account), &(locked + amount).into());
+	storage_write(&account_key("locked_until_key", account), &release_time.into());
+}
+
+// Limits the tokens held back and locked to the account's reduced balance,
+// so they don't hold back tokens it receives later
+fn clamp_unavailable(account: &Address, balance: U256) {
+	let first_key = account_key(HOLD_KEYS[1], account);
+	let latest_key = account_key(HOLD_KEYS[3], account);
+	let locked_key = account_key("locked_amount_key", account);
+	let at_most = |amount: U256, limit: U256| if amount < limit { amount } else { limit };
+	let first = at_most(read_u256(&first_key), balance);
+	let latest = at_most(read_u256(&latest_key), balance - first);
+	let locked = at_most(read_u256(&locked_key), balance);
+	storage_write(&first_key, &first.into());
+	storage_write(&latest_key, &latest.into());
+	storage_write(&locked_key, &locked.into());
+}
+
+// Amount of the account's tokens which can't be transferred yet, held or locked
+fn unavailable_tokens(account: &Address) -> U256 {
+	held_tokens(account) + locked_tokens(account)
+}
+
+// Scale of the accumulated reward per token, so fractions of a token don't get lost
+fn reward_precision() -> U256 {
+	U256::from(1_000_000_000_000_000_000u64)
+}
+
+// Rewards the account earned, both settled and accrued since the last settlement.
+// The contract itself holds the undistributed rewards and never earns any.
+fn earned_rewards(account: &Address, reward_per_token: U256) -> U256 {
+	let settled = read_u256(&account_key("rewards_key", account));
+	if *account == eth::address() {
+		return settled;
+	}
+	let paid = read_u256(&account_key("reward_paid_key", account));
+	// `depositReward` keeps the product within range for the total supply. It can only overflow after
+	// the owner raised the supply with `adminAdjustBalance`, saturating keeps transfers working then.
+	let accrued = read_balance_of(account).checked_mul(reward_per_token - paid)
+		.map_or(U256::max_value(), |product| product / reward_precision());
+	settled.saturating_add(accrued)
+}
+
+// Settles the rewards the account earned with its current balance.
+// Has to run before every change of the account's balance.
+fn settle_rewards(account: &Address) {
+	let reward_per_token = read_u256(&reward_per_token_key());
+	if reward_per_token.is_zero() || *account == eth::address() {
+		return;
+	}
+	let earned = earned_rewards(account, reward_per_token);
+	storage_write(&account_key("rewards_key", account), &earned.into());
+	storage_write(&account_key("reward_paid_key", account), &reward_per_token.into());
+}
+
+// Do the owner's restrictions allow the account to send the amount to the recipient?
+fn transfer_permitted(from: &Address, to: &Address, amount: U256) -> bool {
+	receipt_permitted(to, amount) && debit_permitted(from, amount)
+}
+
+// May the recipient be credited the amount? Paths that credit several recipients check each share.
+fn receipt_permitted(to: &Address, amount: U256) -> bool {
+	if recipient_blocked(to) || !read_u256(&account_key("receive_disabled_key", to)).is_zero() {
+		return false;
+	}
+	if *to == eth::address() && !self_transfer_permitted(amount) {
+		return false;
+	}
+	is_whole_units(amount)
+}
+
+// May the account be debited the amount? Paths that credit several recipients check the total.
+fn debit_permitted(from: &Address, amount: U256) -> bool {
+	let held = unavailable_tokens(from);
+	if !held.is_zero() && read_balance_of(from).saturating_sub(held) < amount {
+		return false;
+	}
+	let retained = read_u256(&account_key("min_retained_key", from));
+	if !retained.is_zero() && read_balance_of(from).saturating_sub(amount) < retained {
+		return false;
+	}
+	!block_limit_reached(from)
+}
+
+// Did the account make as many transfers in the current block as it may?
+fn block_limit_reached(account: &Address) -> bool {
+	let block_limit = read_u256(&block_transfer_limit_key());
+	!block_limit.is_zero() && read_block_transfers(account) >= block_limit
+		&& read_u256(&account_key("block_limit_exempt_key", account)).is_zero()
+}
+
+// What transfers do with tokens sent to the contract itself
+const SELF_TRANSFER_ACCEPT: u8 = 0; // keep them in the treasury
+const SELF_TRANSFER_REJECT: u8 = 1;
+const SELF_TRANSFER_BURN: u8 = 2;
+
+fn read_self_transfer_mode() -> u8 {
+	read_u256(&self_transfer_mode_key()).low_u64() as u8
+}
+
+// May the amount be sent to the contract itself?
+// Burning it mustn't take the total supply below the floor.
+fn self_transfer_permitted(amount: U256) -> bool {
+	match read_self_transfer_mode() {
+		SELF_TRANSFER_ACCEPT => true,
+		SELF_TRANSFER_REJECT => false,
+		SELF_TRANSFER_BURN => read_u256(&total_supply_key()) - amount >= read_u256(&supply_floor_key()),
+		_ => true,
+	}
+}
+
+// Burns tokens just credited to the contract itself if it's in burn mode.
+// Returns whether they were burnt, the caller then emits the Transfer to the zero address.
+fn burn_self_credit(to: &Address, amount: U256) -> bool {
+	if *to != eth::address() || read_self_transfer_mode() != SELF_TRANSFER_BURN {
+		return false;
+	}
+	burn_tokens(to, amount);
+	true
+}
+
+// Burns tokens of the account, reducing the total supply
+fn burn_tokens(account: &Address, amount: U256) {
+	#[cfg(test)]
+	let (balance_before, supply_before) = (sum_balances(&[*account]), supply_unmetered());
+	settle_rewards(account);
+	let balance = read_balance_of(account);
+	storage_write(&balance_key(account), &(balance - amount).into());
+	let total_supply = read_u256(&total_supply_key());
+	storage_write(&total_supply_key(), &(total_supply - amount).into());
+	let burned_before = read_u256(&total_burned_key());
+	storage_write(&total_burned_key(), &(burned_before + amount).into());
+	#[cfg(test)]
+	assert_supply_follows(&[*account], balance_before, supply_before);
+}
+
+// Is the amount a multiple of the transfer unit? Units of 0 and 1 allow any amount.
+fn is_whole_units(amount: U256) -> bool {
+	let unit = read_u256(&transfer_unit_key());
+	unit <= U256::one() || (amount % unit).is_zero()
+}
+
+// Increments the number of outgoing transfers of the account
+fn count_transfer(account: &Address) {
+	let key = account_key("transfer_count_key", account);
+	let count = read_u256(&key);
+	storage_write(&key, &count.saturating_add(1.into()).into());
+	if !read_u256(&block_transfer_limit_key()).is_zero() {
+		let key = block_transfers_key(account, eth::block_number());
+		let count = read_u256(&key);
+		storage_write(&key, &(count + 1).into());
+	}
+}
+
+/// Computes the address a contract gets when deployed with CREATE2:
+/// the last 20 bytes of keccak(0xff ++ deployer ++ salt ++ keccak(init_code)).
+#[cfg(feature = "std")]
+pub fn compute_create2_address(deployer: Address, salt: H256, init_code_hash: H256) -> Address {
+	let mut keccak = Keccak::new_keccak256();
+	let mut res = H256::zero();
+	keccak.update(&[0xff]);
+	keccak.update(deployer.as_ref());
+	keccak.update(salt.as_ref());
+	keccak.update(init_code_hash.as_ref());
+	keccak.finalize(&mut res[..]);
+	Address::from(res)
+}
+
+// State-changing methods `multicall` can run. Their arguments all take one 32-byte word.
+// Methods with dynamic arguments aren't supported, which also keeps out nested `multicall` calls.
+const MULTICALL_METHODS: [&'static str; 32] = [
+	"transfer(address,uint256)",
+	"transferFrom(address,address,uint256)",
+	"transferFromWithFee(address,address,uint256,uint256,address)",
+	"approve(address,uint256)",
+	"approvePeriodic(address,uint256,uint256)",
+	"setAllowanceAcceptance(bool)",
+	"acceptAllowance(address)",
+	"internalTransfer(address,address,uint256)",
+	"blockRecipient(address,bool)",
+	"approveWithTxLimit(address,uint256,uint256)",
+	"transferSplit(address,address,uint256,uint256)",
+	"approveDecaying(address,uint256,uint256)",
+	"emergencyEvacuate(address)",
+	"setBlockTransferLimit(uint256)",
+	"setHoldingPeriod(uint256)",
+	"depositReward(uint256)",
+	"claimReward()",
+	"setMinApproval(uint256)",
+	"renounceOwnership()",
+	"setTransferUnit(uint256)",
+	"approveOnce(address,uint256)",
+	"setReceiveEnabled(bool)",
+	"setSupplyFloor(uint256)",
+	"pruneHolder(address)",
+	"setBlockLimitExempt(address,bool)",
+	"approveWithUses(address,uint256,uint32)",
+	"setMinRetained(address,uint256)",
+	"setMaxAllowancePerSpender(uint256)",
+	"transferAndLock(address,uint256,uint256)",
+	"transferWithMemo(address,uint256,bytes32)",
+	"setSelfTransferMode(uint8)",
+	"adminAdjustBalance(address,uint256)",
+];
+
+// Is the ABI-encoded argument word a valid value of the type? Smaller types are padded with zeros.
+fn canonical_word(kind: &str, word: &[u8]) -> bool {
+	let padding = match kind {
+		"address" => 12,
+		"bool" | "uint8" => 31,
+		"uint32" => 28,
+		_ => 0,
+	};
+	word[..padding].iter().all(|byte| *byte == 0) && (kind != "bool" || word[31] <= 1)
+}
+
+// Can `multicall` dispatch the call? Its method has to be supported and its arguments complete
+// and valid, dispatching anything else could abort the whole transaction.
+fn multicall_supported(call: &[u8]) -> bool {
+	if call.len() < 4 {
+		return false;
+	}
+	let id = (call[0] as u32) << 24 | (call[1] as u32) << 16 | (call[2] as u32) << 8 | call[3] as u32;
+	let signature = match MULTICALL_METHODS.iter().find(|signature| selector(signature) == id) {
+		Some(signature) => signature,
+		None => return false,
+	};
+	let kinds = signature[signature.find('(').unwrap_or(0) + 1..signature.len() - 1]
+		.split(',').filter(|kind| !kind.is_empty());
+	call.len() == 4 + 32 * kinds.clone().count()
+		&& kinds.zip(call[4..].chunks(32)).all(|(kind, word)| canonical_word(kind, word))
+}
+
+// The four-byte ABI selector of a function signature: the first bytes of its keccak hash
+fn selector(signature: &str) -> u32 {
+	let mut keccak = Keccak::new_keccak256();
+	let mut res = [0u8; 32];
+	keccak.update(signature.as_bytes());
+	keccak.finalize(&mut res);
+	(res[0] as u32) << 24 | (res[1] as u32) << 16 | (res[2] as u32) << 8 | res[3] as u32
+}
+
+/// An amount of tokens in raw units, as the contract counts them.
+/// Keeps off-chain callers from passing whole tokens where raw units are expected.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenAmount(U256);
+
+#[cfg(feature = "std")]
+impl TokenAmount {
+	/// The amount of `whole` tokens of a token with `decimals` decimals,
+	/// or `None` if it doesn't fit in 256 bits
+	pub fn from_units(whole: u64, decimals: u8) -> Option<TokenAmount> {
+		let mut raw = U256::from(whole);
+		for _ in 0..decimals {
+			raw = raw.checked_mul(10.into())?;
+		}
+		Some(TokenAmount(raw))
+	}
+
+	/// The amount in raw units, to pass to the contract
+	pub fn as_raw(&self) -> U256 {
+		self.0
+	}
+}
+
+#[cfg(feature = "std")]
+impl From<U256> for TokenAmount {
+	fn from(raw: U256) -> TokenAmount {
+		TokenAmount(raw)
+	}
+}
+
+// Computes amount * bps / 10000 (rounded down) without overflowing
+fn bps_of(amount: U256, bps: U256) -> U256 {
+	let basis = U256::from(10000);
+	amount / basis * bps + amount % basis * bps / basis
+}
+
+pub struct TokenContractInstance;
+
+impl TokenContract for TokenContractInstance {
+	fn constructor(&mut self, total_supply: U256) {
+		// Never initialize twice, e.g. when deployed behind a proxy
+		if !read_u256(&initialized_key()).is_zero() {
+			return;
+		}
+		storage_write(&initialized_key(), &U256::one().into());
+		let sender = eth::sender();
+		// Set up the total supply for the token
+		storage_write(&total_supply_key(), &total_supply.into());
+		storage_write(&original_supply_key(), &total_supply.into());
+		// Give all tokens to the contract owner
+		storage_write(&balance_key(&sender), &total_supply.into());
+		// Set the contract owner
+		storage_write(&owner_key(), &H256::from(sender).into());
+		if !total_supply.is_zero() {
+			add_holder(&sender);
+		}
+	}
+
+	fn balanceOf(&mut self, owner: Address) -> U256 {
+		read_balance_of(&owner)
+	}
+
+	fn totalSupply(&mut self) -> U256 {
+		U256::from_big_endian(&storage_read(&total_supply_key()))
+	}
+
+	fn transfer(&mut self, to: Address, amount: U256) -> bool {
+		let sender = eth::sender();
+		let senderBalance = read_balance_of(&sender);
+		let recipientBalance = read_balance_of(&to);
+	if amount == 0.into() || senderBalance < amount || to == sender || !transfer_permitted(&sender, &to, amount) {
+			false
+		} else {
+			let new_sender_balance = senderBalance - amount;
+			let new_recipient_balance = recipientBalance + amount;
+			settle_rewards(&sender);
+			settle_rewards(&to);
+			// TODO: impl From<U256> for H256 makes convertion to big endian. Could be optimized
+			storage_write(&balance_key(&sender), &new_sender_balance.into());
+			storage_write(&balance_key(&to), &new_recipient_balance.into());
+			#[cfg(test)]
+			assert_conserved(&sender, &to, senderBalance, recipientBalance);
+			add_holder(&to);
+			hold_received(&to, amount);
+			count_transfer(&sender);
+			self.Transfer(sender, to, amount);
+			if burn_self_credit(&to, amount) {
+				self.Transfer(to, Address::zero(), amount);
+			}
+			true
+		}
+	}
+
+	fn approve(&mut self, spender: Address, value: U256) -> bool {
+		if !approval_permitted(&eth::sender(), &spender, value) {
+			return false;
+		}
+		set_allowance(&eth::sender(), &spender, value);
+		add_approving_owner(&eth::sender(), &spender, value);
+		write_allowance_flags(&eth::sender(), &spender, 0);
+		self.Approval(eth::sender(), spender, value);
+		true
+	}
+
+	fn approvePeriodic(&mut self, spender: Address, cap_per_epoch: U256, epoch_seconds: U256) -> bool {
+		let sender = eth::sender();
+		if epoch_seconds.is_zero() || !approval_permitted(&sender, &spender, cap_per_epoch) {
+			return false;
+		}
+		set_allowance(&sender, &spender, cap_per_epoch);
+		add_approving_owner(&sender, &spender, cap_per_epoch);
+		write_allowance_flags(&sender, &spender, PERIODIC_ALLOWANCE);
+		write_periodic_allowance(&sender, &spender, &PeriodicAllowance {
+			cap: cap_per_epoch,
+			epoch: epoch_seconds,
+			epoch_start: U256::from(eth::timestamp()),
+			spent: 0.into(),
+		});
+		self.Approval(sender, spender, cap_per_epoch);
+		true
+	}
+
+	fn allowance(&mut self, owner: Address, spender: Address) -> U256 {
+		if read_allowance_flags(&owner, &spender) & DECAYING_ALLOWANCE != 0 {
+			read_decayed_allowance(&owner, &spender)
+		} else {
+			read_allowance(&allowance_key(&owner, &spender))
+		}
+	}
+
+	fn transferFrom(&mut self, from: Address, to: Address, amount: U256) -> bool {
+		let fromBalance = read_balance_of(&from);
+		let recipientBalance = read_balance_of(&to);
+		let allowed = spendable_allowance(&from, &eth::sender());
+		if  allowed < amount || amount == 0.into() || fromBalance < amount  || to == from || !transfer_permitted(&from, &to, amount) {
+			false
+		} else {
+			let new_from_balance = fromBalance - amount;
+			let new_recipient_balance = recipientBalance + amount;
+			spend_allowance(&from, &eth::sender(), amount);
+			settle_rewards(&from);
+			settle_rewards(&to);
+			storage_write(&balance_key(&from), &new_from_balance.into());
+			storage_write(&balance_key(&to), &new_recipient_balance.into());
+			#[cfg(test)]
+			assert_conserved(&from, &to, fromBalance, recipientBalance);
+			add_holder(&to);
+			hold_received(&to, amount);
+			count_transfer(&from);
+			self.Transfer(from, to, amount);
+			if burn_self_credit(&to, amount) {
+				self.Transfer(to, Address::zero(), amount);
+			}
+			true
+		}
+	}
+
+	fn transferFromWithFee(&mut self, from: Address, to: Address, amount: U256, fee: U256, fee_to: Address) -> bool {
+		let total = match amount.checked_add(fee) {
+			Some(total) => total,
+			None => return false,
+		};
+		let allowed = spendable_allowance(&from, &eth::sender());
+		if allowed < total || amount == 0.into() || read_balance_of(&from) < total || to == from || fee_to == from
+			|| !receipt_permitted(&to, amount) || !receipt_permitted(&fee_to, fee) || !debit_permitted(&from, total)
+			|| (to == fee_to && !receipt_permitted(&to, total)) {
+			false
+		} else {
+			spend_allowance(&from, &eth::sender(), total);
+			move_tokens(&from, &to, amount);
+			count_transfer(&from);
+			self.Transfer(from, to, amount);
+			if burn_self_credit(&to, amount) {
+				self.Transfer(to, Address::zero(), amount);
+			}
+			if !fee.is_zero() {
+				move_tokens(&from, &fee_to, fee);
+				self.Transfer(from, fee_to, fee);
+				if burn_self_credit(&fee_to, fee) {
+					self.Transfer(fee_to, Address::zero(), fee);
+				}
+			}
+			true
+		}
+	}
+
+	fn isSlotOccupied(&mut self, account: Address) -> bool {
+		!read_balance_of(&account).is_zero()
+	}
+
+	fn allHolders(&mut self) -> Vec<Address> {
+		read_holders()
+	}
+
+	fn burnBatch(&mut self, accounts: Vec<Address>, amounts: Vec<U256>) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_BURN) || accounts.len() != amounts.len() || accounts.len() > MAX_BATCH_SIZE {
+			return false;
+		}
+		// Work out every burn first, capped at what the account has left, to check the supply floor
+		let mut burns: Vec<U256> = Vec::with_capacity(accounts.len());
+		for (i, (account, amount)) in accounts.iter().zip(amounts.iter()).enumerate() {
+			let burned_earlier = accounts[..i].iter().zip(burns.iter())
+				.filter(|&(earlier, _)| earlier == account)
+				.fold(U256::zero(), |sum, (_, burned)| sum + *burned);
+			let left = read_balance_of(account) - burned_earlier;
+			burns.push(if *amount < left { *amount } else { left });
+		}
+		let total_burned = burns.iter().fold(U256::zero(), |sum, burned| sum + *burned);
+		let total_supply = read_u256(&total_supply_key());
+		if total_supply - total_burned < read_u256(&supply_floor_key()) {
+			return false;
+		}
+		#[cfg(test)]
+		let (burned_accounts, balances_before) = (accounts.clone(), sum_balances(&accounts));
+		for (account, burned) in accounts.into_iter().zip(burns.into_iter()) {
+			if burned.is_zero() {
+				continue;
+			}
+			settle_rewards(&account);
+			let balance = read_balance_of(&account);
+			storage_write(&balance_key(&account), &(balance - burned).into());
+			self.Transfer(account, Address::zero(), burned);
+		}
+		storage_write(&total_supply_key(), &(total_supply - total_burned).into());
+		let burned_before = read_u256(&total_burned_key());
+		storage_write(&total_burned_key(), &(burned_before + total_burned).into());
+		#[cfg(test)]
+		assert_supply_follows(&burned_accounts, balances_before, total_supply);
+		true
+	}
+
+	fn totalBurned(&mut self) -> U256 {
+		read_u256(&total_burned_key())
+	}
+
+	fn reconcile(&mut self) -> bool {
+		let mut sum = read_u256(&total_burned_key());
+		for holder in read_holders() {
+			sum = match sum.checked_add(read_balance_of(&holder)) {
+				Some(sum) => sum,
+				None => return false,
+			};
+		}
+		// Tokens removed by adjustments on one side, those added on the other
+		match (sum.checked_add(read_u256(&adjusted_down_key())),
+			read_u256(&original_supply_key()).checked_add(read_u256(&adjusted_up_key()))) {
+			(Some(sum), Some(supply)) => sum == supply,
+			_ => false,
+		}
+	}
+
+	fn setAllowanceAcceptance(&mut self, required: bool) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_CONFIGURE) {
+			return false;
+		}
+		storage_write(&allowance_acceptance_key(), &U256::from(required as u64).into());
+		true
+	}
+
+	fn allowanceAcceptanceRequired(&mut self) -> bool {
+		allowance_acceptance_required()
+	}
+
+	fn acceptAllowance(&mut self, owner: Address) -> bool {
+		let spender = eth::sender();
+		if read_allowance(&allowance_key(&owner, &spender)).is_zero() {
+			return false;
+		}
+		let flags = read_allowance_flags(&owner, &spender);
+		write_allowance_flags(&owner, &spender, flags | ACCEPTED_ALLOWANCE);
+		true
+	}
+
+	fn allowanceStatus(&mut self, owner: Address, spender: Address) -> u8 {
+		if read_allowance(&allowance_key(&owner, &spender)).is_zero() {
+			0
+		} else if read_allowance_flags(&owner, &spender) & ACCEPTED_ALLOWANCE == 0 && allowance_acceptance_required() {
+			1
+		} else {
+			2
+		}
+	}
+
+	fn internalTransfer(&mut self, from: Address, to: Address, amount: U256) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_INTERNAL_TRANSFER) || amount == 0.into()
+			|| read_balance_of(&from) < amount || to == from {
+			return false;
+		}
+		move_tokens(&from, &to, amount);
+		true
+	}
+
+	fn transferCount(&mut self, account: Address) -> U256 {
+		read_u256(&account_key("transfer_count_key", &account))
+	}
+
+	fn blockRecipient(&mut self, recipient: Address, blocked: bool) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_BLOCK_RECIPIENT) {
+			return false;
+		}
+		storage_write(&account_key("blocked_recipient_key", &recipient), &U256::from(blocked as u64).into());
+		true
+	}
+
+	fn isRecipientBlocked(&mut self, recipient: Address) -> bool {
+		recipient_blocked(&recipient)
+	}
+
+	fn initParams(&mut self) -> U256 {
+		read_u256(&original_supply_key())
+	}
+
+	fn approveWithTxLimit(&mut self, spender: Address, total: U256, per_tx: U256) -> bool {
+		let sender = eth::sender();
+		if per_tx.is_zero() || !approval_permitted(&sender, &spender, total) {
+			return false;
+		}
+		set_allowance(&sender, &spender, total);
+		add_approving_owner(&sender, &spender, total);
+		write_allowance_flags(&sender, &spender, TX_LIMITED_ALLOWANCE);
+		storage_write(&pair_key("tx_limit_key", &sender, &spender), &per_tx.into());
+		self.Approval(sender, spender, total);
+		true
+	}
+
+	fn txLimit(&mut self, owner: Address, spender: Address) -> U256 {
+		if read_allowance_flags(&owner, &spender) & TX_LIMITED_ALLOWANCE == 0 {
+			return 0.into();
+		}
+		read_u256(&pair_key("tx_limit_key", &owner, &spender))
+	}
+
+	fn isAuthorizedFor(&mut self, account: Address, action: u8) -> bool {
+		is_authorized(&account, action)
+	}
+
+	fn transferSplit(&mut self, primary: Address, secondary: Address, amount: U256, secondary_bps: U256) -> bool {
+		let sender = eth::sender();
+		if secondary_bps > 10000.into() || amount == 0.into() || read_balance_of(&sender) < amount
+			|| primary == sender || secondary == sender {
+			return false;
+		}
+		let secondary_amount = bps_of(amount, secondary_bps);
+		let primary_amount = amount - secondary_amount;
+		if !receipt_permitted(&primary, primary_amount) || !receipt_permitted(&secondary, secondary_amount)
+			|| !debit_permitted(&sender, amount) || (primary == secondary && !receipt_permitted(&primary, amount)) {
+			return false;
+		}
+		if !primary_amount.is_zero() {
+			move_tokens(&sender, &primary, primary_amount);
+			self.Transfer(sender, primary, primary_amount);
+			if burn_self_credit(&primary, primary_amount) {
+				self.Transfer(primary, Address::zero(), primary_amount);
+			}
+		}
+		if !secondary_amount.is_zero() {
+			move_tokens(&sender, &secondary, secondary_amount);
+			self.Transfer(sender, secondary, secondary_amount);
+			if burn_self_credit(&secondary, secondary_amount) {
+				self.Transfer(secondary, Address::zero(), secondary_amount);
+			}
+		}
+		count_transfer(&sender);
+		true
+	}
+
+	fn approveDecaying(&mut self, spender: Address, value: U256, decay_per_second: U256) -> bool {
+		let sender = eth::sender();
+		if !approval_permitted(&sender, &spender, value) {
+			return false;
+		}
+		set_allowance(&sender, &spender, value);
+		add_approving_owner(&sender, &spender, value);
+		write_allowance_flags(&sender, &spender, DECAYING_ALLOWANCE);
+		storage_write(&pair_key("decay_start_key", &sender, &spender), &U256::from(eth::timestamp()).into());
+		storage_write(&pair_key("decay_rate_key", &sender, &spender), &decay_per_second.into());
+		self.Approval(sender, spender, value);
+		true
+	}
+
+	fn effectiveAllowance(&mut self, owner: Address, spender: Address) -> U256 {
+		spendable_allowance(&owner, &spender)
+	}
+
+	fn distributeTreasury(&mut self, recipients: Vec<Address>, shares: Vec<U256>) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_DISTRIBUTE_TREASURY) || recipients.is_empty()
+			|| recipients.len() != shares.len() || recipients.len() > MAX_BATCH_SIZE {
+			return false;
+		}
+		let mut total_shares = U256::zero();
+		for share in shares.iter() {
+			total_shares = match total_shares.checked_add(*share) {
+				Some(total_shares) => total_shares,
+				None => return false,
+			};
+		}
+		let treasury = eth::address();
+		// Undistributed rewards are held by the contract as well, but aren't part of the treasury
+		let balance = read_balance_of(&treasury).saturating_sub(read_u256(&reward_pool_key()));
+		if total_shares.is_zero() || recipients.contains(&treasury) {
+			return false;
+		}
+		let mut payouts = Vec::with_capacity(recipients.len());
+		let mut distributed = U256::zero();
+		for share in shares.iter() {
+			let payout = match balance.checked_mul(*share) {
+				Some(product) => product / total_shares,
+				None => return false,
+			};
+			distributed = distributed + payout;
+			payouts.push(payout);
+		}
+		// Integer division leaves dust, which goes to the last recipient
+		let last = payouts.len() - 1;
+		payouts[last] = payouts[last] + (balance - distributed);
+		// Payouts follow the same rules for receiving tokens as transfers
+		if recipients.iter().zip(payouts.iter()).any(|(recipient, payout)| !receipt_permitted(recipient, *payout)) {
+			return false;
+		}
+		for (recipient, payout) in recipients.into_iter().zip(payouts.into_iter()) {
+			if !payout.is_zero() {
+				move_tokens(&treasury, &recipient, payout);
+				self.Transfer(treasury, recipient, payout);
+			}
+		}
+		true
+	}
+
+	fn emergencyEvacuate(&mut self, to: Address) -> bool {
+		let treasury = eth::address();
+		if !is_authorized(&eth::sender(), ACTION_EMERGENCY_EVACUATE) || to == treasury {
+			return false;
+		}
+		// The tokens follow the same rules for receiving them as transfers
+		let balance = read_balance_of(&treasury).saturating_sub(read_u256(&reward_pool_key()));
+		if !receipt_permitted(&to, balance) {
+			return false;
+		}
+		// Ether first, nothing is moved if sending it fails.
+		// Enough gas for a multisig wallet receiving it.
+		let value = eth::balance(&treasury);
+		if !value.is_zero() && eth::call(50000, &to, value, &[], &mut []).is_err() {
+			return false;
+		}
+		if !balance.is_zero() {
+			move_tokens(&treasury, &to, balance);
+			self.Transfer(treasury, to, balance);
+		}
+		true
+	}
+
+	fn setBlockTransferLimit(&mut self, limit: U256) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_CONFIGURE) {
+			return false;
+		}
+		storage_write(&block_transfer_limit_key(), &limit.into());
+		true
+	}
+
+	fn transfersThisBlock(&mut self, account: Address) -> u32 {
+		read_block_transfers(&account).low_u32()
+	}
+
+	fn setHoldingPeriod(&mut self, seconds: U256) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_CONFIGURE) {
+			return false;
+		}
+		storage_write(&holding_period_key(), &seconds.into());
+		true
+	}
+
+	fn unlockTime(&mut self, account: Address) -> U256 {
+		let received_at_key = match held_batches(&account) {
+			(first, _) if !first.is_zero() => HOLD_KEYS[0],
+			(_, latest) if !latest.is_zero() => HOLD_KEYS[2],
+			_ => return 0.into(),
+		};
+		read_u256(&account_key(received_at_key, &account)) + read_u256(&holding_period_key())
+	}
+
+	fn isInitialized(&mut self) -> bool {
+		!read_u256(&initialized_key()).is_zero()
+	}
+
+	fn spendCapacity(&mut self, owner: Address, spender: Address) -> U256 {
+		let balance = read_balance_of(&owner).saturating_sub(unavailable_tokens(&owner));
+		let allowed = spendable_allowance(&owner, &spender);
+		if balance < allowed { balance } else { allowed }
+	}
+
+	fn owner(&mut self) -> Address {
+		read_owner()
+	}
+
+	fn verifyOwnerBalance(&mut self, expected: U256) -> bool {
+		let owner = self.owner();
+		read_balance_of(&owner) == expected
+	}
+
+	fn depositReward(&mut self, amount: U256) -> bool {
+		let sender = eth::sender();
+		let pool = eth::address();
+		if !is_authorized(&sender, ACTION_DEPOSIT_REWARD) || amount == 0.into() || read_balance_of(&sender) < amount {
+			return false;
+		}
+		// Tokens held by the contract (rewards and treasury) don't earn rewards,
+		// including the deposit once it is moved there
+		let eligible_supply = read_u256(&total_supply_key()) - read_balance_of(&pool) - amount;
+		let added = match amount.checked_mul(reward_precision()) {
+			Some(scaled) if !eligible_supply.is_zero() => scaled / eligible_supply,
+			_ => return false,
+		};
+		// A deposit too small to add anything would be stuck in the pool. The accumulator times
+		// the total supply bounds every holder's accrued rewards, so it mustn't overflow.
+		let reward_per_token = match read_u256(&reward_per_token_key()).checked_add(added) {
+			Some(reward_per_token) if !added.is_zero() => reward_per_token,
+			_ => return false,
+		};
+		if reward_per_token.checked_mul(read_u256(&total_supply_key())).is_none() {
+			return false;
+		}
+		// Settles the sender's rewards before the new ones are added
+		move_tokens(&sender, &pool, amount);
+		let reward_pool = read_u256(&reward_pool_key()) + amount;
+		storage_write(&reward_pool_key(), &reward_pool.into());
+		storage_write(&reward_per_token_key(), &reward_per_token.into());
+		self.Transfer(sender, pool, amount);
+		true
+	}
+
+	fn claimReward(&mut self) -> U256 {
+		let sender = eth::sender();
+		let pool = eth::address();
+		settle_rewards(&sender);
+		let reward = read_u256(&account_key("rewards_key", &sender));
+		if reward.is_zero() || sender == pool {
+			return 0.into();
+		}
+		storage_write(&account_key("rewards_key", &sender), &[0u8; 32]);
+		storage_write(&reward_pool_key(), &(read_u256(&reward_pool_key()) - reward).into());
+		move_tokens(&pool, &sender, reward);
+		self.Transfer(pool, sender, reward);
+		reward
+	}
+
+	fn earned(&mut self, account: Address) -> U256 {
+		earned_rewards(&account, read_u256(&reward_per_token_key()))
+	}
+
+	fn setMinApproval(&mut self, min: U256) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_CONFIGURE) {
+			return false;
+		}
+		storage_write(&min_approval_key(), &min.into());
+		true
+	}
+
+	fn minApproval(&mut self) -> U256 {
+		read_u256(&min_approval_key())
+	}
+
+	fn renounceOwnership(&mut self) -> bool {
+		let owner = eth::sender();
+		if !is_authorized(&owner, ACTION_RENOUNCE_OWNERSHIP) {
+			return false;
+		}
+		storage_write(&owner_key(), &H256::zero().into());
+		self.OwnershipTransferred(owner, Address::zero());
+		true
+	}
+
+	fn setTransferUnit(&mut self, unit: U256) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_CONFIGURE) {
+			return false;
+		}
+		storage_write(&transfer_unit_key(), &unit.into());
+		true
+	}
+
+	fn transferUnit(&mut self) -> U256 {
+		read_u256(&transfer_unit_key())
+	}
+
+	fn rawStorage(&mut self, key: H256) -> H256 {
+		H256::from(storage_read(&key))
+	}
+
+	fn positionOf(&mut self, account: Address) -> (U256, U256, U256) {
+		let claimable = earned_rewards(&account, read_u256(&reward_per_token_key()));
+		(read_balance_of(&account), unavailable_tokens(&account), claimable)
+	}
+
+	fn approveOnce(&mut self, spender: Address, value: U256) -> bool {
+		let sender = eth::sender();
+		if !approval_permitted(&sender, &spender, value) {
+			return false;
+		}
+		set_allowance(&sender, &spender, value);
+		add_approving_owner(&sender, &spender, value);
+		write_allowance_flags(&sender, &spender, ONE_SHOT_ALLOWANCE);
+		self.Approval(sender, spender, value);
+		true
+	}
+
+	fn isOneShot(&mut self, owner: Address, spender: Address) -> bool {
+		read_allowance_flags(&owner, &spender) & ONE_SHOT_ALLOWANCE != 0
+	}
+
+	fn setReceiveEnabled(&mut self, enabled: bool) {
+		storage_write(&account_key("receive_disabled_key", &eth::sender()), &U256::from(!enabled as u64).into());
+	}
+
+	fn receiveEnabled(&mut self, account: Address) -> bool {
+		read_u256(&account_key("receive_disabled_key", &account)).is_zero()
+	}
+
+	fn transferFromBatch(&mut self, froms: Vec<Address>, to: Address, amounts: Vec<U256>) -> bool {
+		let spender = eth::sender();
+		if froms.len() != amounts.len() || froms.len() > MAX_BATCH_SIZE {
+			return false;
+		}
+		// Check every entry before moving anything. Owners are distinct, so the checks can't interfere.
+		let mut total = U256::zero();
+		for (i, (from, amount)) in froms.iter().zip(amounts.iter()).enumerate() {
+			if froms[..i].contains(from) || *from == to || amount.is_zero()
+				|| spendable_allowance(from, &spender) < *amount || read_balance_of(from) < *amount
+				|| !transfer_permitted(from, &to, *amount) {
+				return false;
+			}
+			// Can't overflow, distinct owners hold at most the total supply
+			total = total + *amount;
+		}
+		// Burning everything sent to the contract mustn't take the supply below the floor
+		if to == eth::address() && !self_transfer_permitted(total) {
+			return false;
+		}
+		for (from, amount) in froms.into_iter().zip(amounts.into_iter()) {
+			spend_allowance(&from, &spender, amount);
+			move_tokens(&from, &to, amount);
+			count_transfer(&from);
+			self.Transfer(from, to, amount);
+			if burn_self_credit(&to, amount) {
+				self.Transfer(to, Address::zero(), amount);
+			}
+		}
+		true
+	}
+
+	fn allowanceOccupied(&mut self, owner: Address, spender: Address) -> bool {
+		!read_allowance(&allowance_key(&owner, &spender)).is_zero()
+	}
+
+	fn setSupplyFloor(&mut self, floor: U256) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_CONFIGURE) {
+			return false;
+		}
+		storage_write(&supply_floor_key(), &floor.into());
+		true
+	}
+
+	fn supplyFloor(&mut self) -> U256 {
+		read_u256(&supply_floor_key())
+	}
+
+	fn pruneHolder(&mut self, account: Address) -> bool {
+		if read_u256(&account_key("holder_position_key", &account)).is_zero() || !read_balance_of(&account).is_zero() {
+			return false;
+		}
+		remove_holder(&account);
+		true
+	}
+
+	fn setBlockLimitExempt(&mut self, account: Address, exempt: bool) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_CONFIGURE) {
+			return false;
+		}
+		storage_write(&account_key("block_limit_exempt_key", &account), &U256::from(exempt as u64).into());
+		true
+	}
+
+	fn isBlockLimitExempt(&mut self, account: Address) -> bool {
+		!read_u256(&account_key("block_limit_exempt_key", &account)).is_zero()
+	}
+
+	fn erc20Selectors(&mut self) -> Vec<u32> {
+		[
+			"balanceOf(address)",
+			"totalSupply()",
+			"transfer(address,uint256)",
+			"transferFrom(address,address,uint256)",
+			"approve(address,uint256)",
+			"allowance(address,address)",
+		].iter().map(|signature| selector(signature)).collect()
+	}
+
+	fn ownersApproving(&mut self, spender: Address) -> Vec<Address> {
+		read_approving_owners(&spender)
+	}
+
+	fn allowancesOfSpender(&mut self, spender: Address) -> Vec<U256> {
+		read_approving_owners(&spender).into_iter().map(|owner| self.allowance(owner, spender)).collect()
+	}
+
+	fn approveWithUses(&mut self, spender: Address, value: U256, max_uses: u32) -> bool {
+		let sender = eth::sender();
+		if max_uses == 0 || !approval_permitted(&sender, &spender, value) {
+			return false;
+		}
+		set_allowance(&sender, &spender, value);
+		add_approving_owner(&sender, &spender, value);
+		write_allowance_flags(&sender, &spender, LIMITED_USES_ALLOWANCE);
+		storage_write(&pair_key("remaining_uses_key", &sender, &spender), &U256::from(max_uses as u64).into());
+		self.Approval(sender, spender, value);
+		true
+	}
+
+	fn remainingUses(&mut self, owner: Address, spender: Address) -> u32 {
+		if read_allowance_flags(&owner, &spender) & LIMITED_USES_ALLOWANCE == 0 {
+			return 0;
+		}
+		read_u256(&pair_key("remaining_uses_key", &owner, &spender)).low_u32()
+	}
+
+	fn config(&mut self) -> (bool, U256, U256, U256, U256, U256, U256, u8) {
+		(
+			allowance_acceptance_required(),
+			read_u256(&block_transfer_limit_key()),
+			read_u256(&holding_period_key()),
+			read_u256(&min_approval_key()),
+			read_u256(&transfer_unit_key()),
+			read_u256(&supply_floor_key()),
+			read_u256(&max_allowance_key()),
+			read_self_transfer_mode(),
+		)
+	}
+
+	fn multicall(&mut self, calls: Vec<Vec<u8>>) -> Vec<bool> {
+		if !read_u256(&multicall_lock_key()).is_zero() || calls.len() > MAX_BATCH_SIZE {
+			return Vec::new();
+		}
+		storage_write(&multicall_lock_key(), &U256::one().into());
+		let mut endpoint = Endpoint::new(TokenContractInstance{});
+		let results = calls.iter().map(|call| {
+			if !multicall_supported(call) {
+				return false;
+			}
+			let result = endpoint.dispatch(call);
+			result.is_empty() || (result.len() >= 32 && result[..32].iter().any(|byte| *byte != 0))
+		}).collect();
+		storage_write(&multicall_lock_key(), &[0u8; 32]);
+		results
+	}
+
+	fn setMinRetained(&mut self, account: Address, min: U256) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_CONFIGURE) {
+			return false;
+		}
+		storage_write(&account_key("min_retained_key", &account), &min.into());
+		true
+	}
+
+	fn minRetained(&mut self, account: Address) -> U256 {
+		read_u256(&account_key("min_retained_key", &account))
+	}
+
+	fn setMaxAllowancePerSpender(&mut self, max: U256) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_CONFIGURE) {
+			return false;
+		}
+		storage_write(&max_allowance_key(), &max.into());
+		true
+	}
+
+	fn maxAllowancePerSpender(&mut self) -> U256 {
+		read_u256(&max_allowance_key())
+	}
+
+	fn allowanceCountOf(&mut self, owner: Address) -> u32 {
+		read_u256(&account_key("allowance_count_key", &owner)).low_u32()
+	}
+
+	fn transferAndLock(&mut self, to: Address, amount: U256, release_time: U256) -> bool {
+		let hold_keys: Vec<H256> = HOLD_KEYS.iter().map(|name| account_key(name, &to)).collect();
+		let holds: Vec<U256> = hold_keys.iter().map(|key| read_u256(key)).collect();
+		if to == eth::address() || !lock_permitted(&to, release_time) || !self.transfer(to, amount) {
+			return false;
+		}
+		// The lock replaces the holding period, so the tokens aren't counted as both held and locked
+		if !read_u256(&holding_period_key()).is_zero() {
+			for (key, value) in hold_keys.iter().zip(holds.into_iter()) {
+				storage_write(key, &value.into());
+			}
+		}
+		lock_tokens(&to, amount, release_time);
+		true
+	}
+
+	fn availableBalance(&mut self, account: Address) -> U256 {
+		read_balance_of(&account).saturating_sub(unavailable_tokens(&account))
+	}
+
+	fn hasTransacted(&mut self, account: Address) -> bool {
+		!read_u256(&account_key("transacted_key", &account)).is_zero()
+	}
+
+	fn maxBatchSize(&mut self) -> u32 {
+		MAX_BATCH_SIZE as u32
+	}
+
+	fn sumHolderBalances(&mut self) -> U256 {
+		read_holders().iter().fold(U256::zero(), |sum, holder| sum.saturating_add(read_balance_of(holder)))
+	}
+
+	fn transferWithMemo(&mut self, to: Address, amount: U256, memo: H256) -> bool {
+		if !self.transfer(to, amount) {
+			return false;
+		}
+		self.TransferMemo(eth::sender(), to, memo);
+		true
+	}
+
+	fn setSelfTransferMode(&mut self, mode: u8) -> bool {
+		if !is_authorized(&eth::sender(), ACTION_CONFIGURE) || mode > SELF_TRANSFER_BURN {
+			return false;
+		}
+		storage_write(&self_transfer_mode_key(), &U256::from(mode as u64).into());
+		true
+	}
+
+	fn selfTransferMode(&mut self) -> u8 {
+		read_self_transfer_mode()
+	}
+
+	fn maxTransferableNow(&mut self, account: Address) -> U256 {
+		if block_limit_reached(&account) {
+			return 0.into();
+		}
+		let balance = read_balance_of(&account);
+		let available = balance.saturating_sub(unavailable_tokens(&account));
+		let above_retained = balance.saturating_sub(read_u256(&account_key("min_retained_key", &account)));
+		let max = if above_retained < available { above_retained } else { available };
+		let unit = read_u256(&transfer_unit_key());
+		if unit > U256::one() { max - max % unit } else { max }
+	}
+
+	fn adminAdjustBalance(&mut self, account: Address, new_balance: U256) -> bool {
+		// The contract's balance has to keep covering the reward pool
+		if !is_authorized(&eth::sender(), ACTION_ADJUST_BALANCE) || account == eth::address() {
+			return false;
+		}
+		let old_balance = read_balance_of(&account);
+		let total_supply = read_u256(&total_supply_key());
+		let new_supply = if new_balance > old_balance {
+			match total_supply.checked_add(new_balance - old_balance) {
+				Some(new_supply) => new_supply,
+				None => return false,
+			}
+		} else {
+			let new_supply = total_supply - (old_balance - new_balance);
+			if new_supply < read_u256(&supply_floor_key()) {
+				return false;
+			}
+			new_supply
+		};
+		// Recorded for `reconcile`
+		let (adjusted_key, difference) = if new_balance > old_balance {
+			(adjusted_up_key(), new_balance - old_balance)
+		} else {
+			(adjusted_down_key(), old_balance - new_balance)
+		};
+		let adjusted = read_u256(&adjusted_key).saturating_add(difference);
+		settle_rewards(&account);
+		storage_write(&balance_key(&account), &new_balance.into());
+		storage_write(&total_supply_key(), &new_supply.into());
+		storage_write(&adjusted_key, &adjusted.into());
+		#[cfg(test)]
+		assert_supply_follows(&[account], old_balance, total_supply);
+		if new_balance < old_balance {
+			clamp_unavailable(&account, new_balance);
+		}
+		if !new_balance.is_zero() {
+			add_holder(&account);
+		}
+		self.AdminAdjustment(account, old_balance, new_balance);
+		true
+	}
+}
+
+#[cfg(test)]
+extern crate pwasm_test;
+
+// Counts the storage reads and writes of the current thread (test is run in)
+#[cfg(test)]
+mod storage_counter {
+	extern crate std;
+	use self::std::cell::Cell;
+
+	thread_local! {
+		static READS: Cell<u32> = Cell::new(0);
+		static WRITES: Cell<u32> = Cell::new(0);
+	}
+
+	pub fn count_read() {
+		READS.with(|reads| reads.set(reads.get() + 1));
+	}
+
+	pub fn count_write() {
+		WRITES.with(|writes| writes.set(writes.get() + 1));
+	}
+
+	// Returns the number of (SLOAD, SSTORE) operations performed by f
+	pub fn measure<F: FnOnce()>(f: F) -> (u32, u32) {
+		READS.with(|reads| reads.set(0));
+		WRITES.with(|writes| writes.set(0));
+		f();
+		(READS.with(|reads| reads.get()), WRITES.with(|writes| writes.get()))
+	}
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+	extern crate std;
+	use super::*;
+	use pwasm_test::{ext_reset, ext_update, ext_get, External};
+	use storage_counter;
+
+	// Topics of the events, keccak hashes of their signatures
+	const TRANSFER_TOPIC: &'static str = "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+	const APPROVAL_TOPIC: &'static str = "8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925";
+	const OWNERSHIP_TRANSFERRED_TOPIC: &'static str = "8be0079c531659141344cd1fd0a4f28419497f9722a3daafe3b4186f6b6457e0";
+	const TRANSFER_MEMO_TOPIC: &'static str = "d7cee7df204a6f751917751138c8cdb5c676cc82662afb461ea7ed6401b1e90e";
+	const ADMIN_ADJUSTMENT_TOPIC: &'static str = "318af28ffbb38b4247eb83e309d7ce6a66211d4ba6683326df65b392d0b60e7a";
+
+	fn addr(a: &'static str) -> Address {
+		a.parse().expect(&format!("parsing of '{}' failed", a))
+	}
+
+	#[test]
+	fn balanceOf_should_return_balance() {
+		ext_reset(|e| e.storage([1,0,0,0,0,0,0,0,0,0,0,0,
+							31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31].into(), U256::from(100000).into()));
+		let address = Address::from([31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31]);
+		let mut contract = TokenContractInstance{};
+		assert_eq!(contract.balanceOf(address), 100000.into());
+	}
+
+	#[test]
+	fn totalSupply_should_return_total_supply_contract_was_initialized_with() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let total_supply = 42.into();
+		contract.constructor(total_supply);
+		assert_eq!(contract.totalSupply(), total_supply);
+	}
+
+	#[test]
+	fn should_succeed_in_creating_max_possible_amount_of_tokens() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		// set total supply to maximum value of an unsigned 256 bit integer
+		let total_supply = U256::from_dec_str("115792089237316195423570985008687907853269984665640564039457584007913129639935").unwrap();
+		assert_eq!(total_supply, U256::max_value());
+		contract.constructor(total_supply);
+		assert_eq!(contract.totalSupply(), total_supply);
+	}
+
+	#[test]
+	fn should_initially_give_the_total_supply_to_the_creator() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let total_supply = 10000.into();
+		contract.constructor(total_supply);
+		assert_eq!(contract.balanceOf(ext_get().sender()), total_supply);
+	}
+
+	#[test]
+	fn should_succeed_transfering_1000_from_owner_to_another_address() {
+		let mut contract = TokenContractInstance{};
+
+		let owner_address = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let sam_address = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+
+		ext_reset(|e| e.sender(owner_address.clone()));
+
+		let total_supply = 10000.into();
+		contract.constructor(total_supply);
+
+		assert_eq!(contract.balanceOf(owner_address), total_supply);
+
+		assert_eq!(contract.transfer(sam_address, 1000.into()), true);
+		assert_eq!(ext_get().logs().len(), 1);
+		assert_eq!(ext_get().logs()[0].topics.as_ref(), &[
+			TRANSFER_TOPIC.parse().unwrap(), // hash of the event name
+			"000000000000000000000000ea674fdde714fd979de3edf0f56aa9716b898ec8".parse().unwrap(), // sender address
+			"000000000000000000000000db6fd484cfa46eeeb73c71edee823e4812f9e2e1".parse().unwrap()]); // recipient address
+		assert_eq!(ext_get().logs()[0].data.as_ref(), &[
+			0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 232]);
+		assert_eq!(contract.balanceOf(owner_address), 9000.into());
+		assert_eq!(contract.balanceOf(sam_address), 1000.into());
+	}
+
+	#[test]
+	fn should_return_false_transfer_not_sufficient_funds() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		contract.constructor(10000.into());
+		assert_eq!(contract.transfer(addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1"), 50000.into()), false);
+		assert_eq!(contract.balanceOf(::pwasm_ethereum::sender()), 10000.into());
+		assert_eq!(contract.balanceOf(addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1")), 0.into());
+		assert_eq!(ext_get().logs().len(), 0, "Should be no events created");
+	}
+
+	#[test]
+	fn approve_should_approve() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(40000.into());
+		contract.approve(spender, 40000.into());
+		assert_eq!(ext_get().logs().len(), 1, "Should be 1 event logged");
+		assert_eq!(ext_get().logs()[0].topics.as_ref(), &[
+			APPROVAL_TOPIC.parse().unwrap(), // hash of the event name
+			"0000000000000000000000000000000000000000000000000000000000000000".parse().unwrap(), // sender (owner) address
+			"000000000000000000000000db6fd484cfa46eeeb73c71edee823e4812f9e2e1".parse().unwrap()]); // spender address
+		assert_eq!(contract.allowance(::pwasm_ethereum::sender(), spender.clone()), 40000.into());
+	}
+
+	#[test]
+	fn spender_should_be_able_to_spend_if_allowed() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender: Address = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress: Address = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(40000.into());
+		contract.approve(spender, 10000.into());
+
+		// Build different external with sender = spender
+		ext_update(|e| e.sender(spender));
+
+		assert_eq!(contract.transferFrom(owner.clone(), samAddress.clone(), 5000.into()), true);
+		assert_eq!(contract.balanceOf(samAddress.clone()), 5000.into());
+		assert_eq!(contract.balanceOf(owner.clone()), 35000.into());
+
+		assert_eq!(contract.transferFrom(owner.clone(), samAddress.clone(), 5000.into()), true);
+		assert_eq!(contract.balanceOf(samAddress.clone()), 10000.into());
+		assert_eq!(contract.balanceOf(owner.clone()), 30000.into());
+
+		// The limit has reached. No more coins should be available to spend for the spender
+		assert_eq!(contract.transferFrom(owner.clone(), samAddress.clone(), 1.into()), false);
+		assert_eq!(contract.balanceOf(samAddress.clone()), 10000.into());
+		assert_eq!(contract.balanceOf(owner.clone()), 30000.into());
+		assert_eq!(ext_get().logs().len(), 3, "Two events should be created");
+	}
+
+	#[test]
+	fn spender_should_not_be_able_to_spend_if_owner_has_no_coins() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(70000.into());
+		contract.transfer(samAddress, 30000.into());
+		contract.approve(spender, 40000.into());
+
+		// Build different external with sender = spender
+		ext_update(|e| e.sender(spender));
+
+		// Despite of the allowance, can't transfer because the owner is out of tokens
+		assert_eq!(contract.transferFrom(owner.clone(), samAddress.clone(), 40001.into()), false);
+		assert_eq!(contract.balanceOf(samAddress.clone()), 30000.into());
+		assert_eq!(contract.balanceOf(owner.clone()), 40000.into());
+		assert_eq!(ext_get().logs().len(), 2, "Should be no events created");
+	}
+
+  #[test]
+  fn should_not_transfer_to_self() {
+	let mut contract = TokenContractInstance{};
+	let owner_address = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+	ext_reset(|e| e.sender(owner_address.clone()));
+	let total_supply = 10000.into();
+	contract.constructor(total_supply);
+	assert_eq!(contract.balanceOf(owner_address), total_supply);
+	assert_eq!(contract.transfer(owner_address, 1000.into()), false);
+	assert_eq!(contract.transferFrom(owner_address, owner_address, 1000.into()), false);
+	assert_eq!(contract.balanceOf(owner_address), 10000.into());
+	assert_eq!(ext_get().logs().len(), 0);
+  }
+
+	#[test]
+	fn periodic_allowance_should_be_capped_within_an_epoch() {
+		ext_reset(|e| e.timestamp(1000));
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(40000.into());
+		assert_eq!(contract.approvePeriodic(spender, 100.into(), 3600.into()), true);
+		assert_eq!(contract.allowance(owner, spender), 100.into());
+
+		ext_update(|e| e.sender(spender).timestamp(2000));
+
+		assert_eq!(contract.transferFrom(owner, samAddress, 60.into()), true);
+		assert_eq!(contract.transferFrom(owner, samAddress, 40.into()), true);
+		// The cap for this epoch is spent
+		assert_eq!(contract.transferFrom(owner, samAddress, 1.into()), false);
+		assert_eq!(contract.balanceOf(samAddress), 100.into());
+	}
+
+	#[test]
+	fn periodic_allowance_should_refill_in_the_next_epoch() {
+		ext_reset(|e| e.timestamp(1000));
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(40000.into());
+		contract.approvePeriodic(spender, 100.into(), 3600.into());
+
+		ext_update(|e| e.sender(spender).timestamp(4599));
+		assert_eq!(contract.transferFrom(owner, samAddress, 100.into()), true);
+		assert_eq!(contract.transferFrom(owner, samAddress, 1.into()), false);
+
+		// The next epoch starts at 4600
+		ext_update(|e| e.timestamp(4600));
+		assert_eq!(contract.transferFrom(owner, samAddress, 101.into()), false);
+		assert_eq!(contract.transferFrom(owner, samAddress, 100.into()), true);
+		assert_eq!(contract.balanceOf(samAddress), 200.into());
+		assert_eq!(contract.balanceOf(owner), 39800.into());
+	}
+
+	#[test]
+	fn isSlotOccupied_should_reflect_whether_balance_is_nonzero() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		assert_eq!(contract.isSlotOccupied(samAddress), false);
+		contract.transfer(samAddress, 1.into());
+		assert_eq!(contract.isSlotOccupied(samAddress), true);
+	}
+
+	// The mock doesn't provide precompiles, so ecrecover is stubbed to answer a known vector
+	fn ext_with_ecrecover() {
+		ext_reset(|e| e.endpoint(ecrecover_address(), Box::new(|_val, input: &[u8], result: &mut [u8]| {
+			let known_input: Vec<u8> = [
+				"38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e",
+				"000000000000000000000000000000000000000000000000000000000000001b",
+				"38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e",
+				"789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02",
+			].iter().flat_map(|word| word.parse::<H256>().unwrap().as_ref().to_vec()).collect();
+			if input == &known_input[..] {
+				result[12..].copy_from_slice(addr("ceaccac640adf55b2028469bd36ba501f28b699d").as_ref());
+			}
+			Ok(())
+		})));
+	}
+
+	#[test]
+	fn recover_signer_should_recover_known_signer() {
+		ext_with_ecrecover();
+		let digest: H256 = "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e".parse().unwrap();
+		let r = digest.clone();
+		let s: H256 = "789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02".parse().unwrap();
+		assert_eq!(recover_signer(&digest, 27, &r, &s), Some(addr("ceaccac640adf55b2028469bd36ba501f28b699d")));
+	}
+
+	#[test]
+	fn recover_signer_should_return_none_for_malformed_signature() {
+		ext_with_ecrecover();
+		let digest: H256 = "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e".parse().unwrap();
+		let r = digest.clone();
+		let s: H256 = "789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02".parse().unwrap();
+		// Recovery id out of range
+		assert_eq!(recover_signer(&digest, 29, &r, &s), None);
+		// The precompile can't recover a signer
+		assert_eq!(recover_signer(&digest, 28, &r, &s), None);
+	}
+
+	#[test]
+	fn allHolders_should_list_every_account_with_balance_once() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let aliceAddress = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		contract.transfer(bobAddress, 1000.into());
+		contract.transfer(samAddress, 1000.into());
+		// Failed transfers don't add holders
+		contract.transfer(aliceAddress, 0.into());
+		assert_eq!(contract.allHolders(), vec![owner, samAddress, bobAddress]);
+		for holder in contract.allHolders() {
+			assert!(contract.balanceOf(holder) > 0.into());
+		}
+	}
+
+	#[test]
+	fn transferFromWithFee_should_spend_allowance_for_amount_and_fee() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let collector = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		contract.constructor(40000.into());
+		contract.approve(spender, 1100.into());
+
+		ext_update(|e| e.sender(spender));
+
+		assert_eq!(contract.transferFromWithFee(owner, samAddress, 1000.into(), 100.into(), collector), true);
+		assert_eq!(contract.balanceOf(samAddress), 1000.into());
+		assert_eq!(contract.balanceOf(collector), 100.into());
+		assert_eq!(contract.balanceOf(owner), 38900.into());
+		assert_eq!(contract.allowance(owner, spender), 0.into());
+		assert_eq!(ext_get().logs().len(), 3, "Approval and two Transfer events should be created");
+	}
+
+	#[test]
+	fn transferFromWithFee_should_fail_if_fee_goes_back_to_owner() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(40000.into());
+		contract.approve(spender, 1100.into());
+
+		ext_update(|e| e.sender(spender));
+
+		assert_eq!(contract.transferFromWithFee(owner, samAddress, 1000.into(), 100.into(), owner), false);
+		assert_eq!(contract.balanceOf(samAddress), 0.into());
+		assert_eq!(contract.allowance(owner, spender), 1100.into());
+	}
+
+	#[test]
+	fn transferFromWithFee_should_fail_if_allowance_covers_only_amount() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let collector = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		contract.constructor(40000.into());
+		contract.approve(spender, 1000.into());
+
+		ext_update(|e| e.sender(spender));
+
+		assert_eq!(contract.transferFromWithFee(owner, samAddress, 1000.into(), 1.into(), collector), false);
+		assert_eq!(contract.balanceOf(samAddress), 0.into());
+		assert_eq!(contract.balanceOf(collector), 0.into());
+		assert_eq!(contract.allowance(owner, spender), 1000.into());
+		assert_eq!(ext_get().logs().len(), 1, "Only the Approval event should be created");
+	}
+
+	#[test]
+	fn burnBatch_should_burn_from_each_account_and_reduce_total_supply() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		contract.transfer(bobAddress, 500.into());
+
+		// Bob's burn is capped at his balance
+		assert_eq!(contract.burnBatch(vec![samAddress, bobAddress], vec![400.into(), 800.into()]), true);
+		assert_eq!(contract.balanceOf(samAddress), 600.into());
+		assert_eq!(contract.balanceOf(bobAddress), 0.into());
+		assert_eq!(contract.balanceOf(owner), 8500.into());
+		assert_eq!(contract.totalSupply(), 9100.into());
+		assert_eq!(ext_get().logs().len(), 4, "A Transfer to zero should be created per account");
+		assert_eq!(ext_get().logs()[3].topics.as_ref(), &[
+			TRANSFER_TOPIC.parse().unwrap(), // hash of the event name
+			"000000000000000000000000db6fd484cfa46eeeb73c71edee823e4812f9e2e1".parse().unwrap(), // burned account
+			"0000000000000000000000000000000000000000000000000000000000000000".parse().unwrap()]); // zero address
+	}
+
+	#[test]
+	fn burnBatch_should_reject_mismatched_lengths_and_non_owner() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+
+		assert_eq!(contract.burnBatch(vec![samAddress], vec![400.into(), 400.into()]), false);
+
+		ext_update(|e| e.sender(samAddress));
+		assert_eq!(contract.burnBatch(vec![samAddress], vec![400.into()]), false);
+
+		assert_eq!(contract.balanceOf(samAddress), 1000.into());
+		assert_eq!(contract.totalSupply(), 10000.into());
+	}
+
+	#[test]
+	fn event_topics_should_match_event_signatures() {
+		fn topic(signature: &str) -> H256 {
+			let mut keccak = Keccak::new_keccak256();
+			let mut res = H256::zero();
+			keccak.update(signature.as_ref());
+			keccak.finalize(&mut res[..]);
+			res
+		}
+		assert_eq!(topic("Transfer(address,address,uint256)"), TRANSFER_TOPIC.parse::<H256>().unwrap());
+		assert_eq!(topic("Approval(address,address,uint256)"), APPROVAL_TOPIC.parse::<H256>().unwrap());
+		assert_eq!(topic("OwnershipTransferred(address,address)"), OWNERSHIP_TRANSFERRED_TOPIC.parse::<H256>().unwrap());
+		assert_eq!(topic("TransferMemo(address,address,bytes32)"), TRANSFER_MEMO_TOPIC.parse::<H256>().unwrap());
+		assert_eq!(topic("AdminAdjustment(address,uint256,uint256)"), ADMIN_ADJUSTMENT_TOPIC.parse::<H256>().unwrap());
+	}
+
+	#[test]
+	fn transfer_should_not_exceed_storage_ops_baseline() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		// sender and recipient balances, the recipient blocklist and opt-out, transfer unit, holding period,
+		// lock, retained minimum and per-block limit, the reward accumulator for both accounts, the holders
+		// list lookup, the holding period again, the transfer counter and the per-block limit again
+		assert_eq!(storage_counter::measure(|| { contract.transfer(samAddress, 1000.into()); }), (15, 3));
+	}
+
+	#[test]
+	fn transferFrom_should_not_exceed_storage_ops_baseline() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		contract.approve(spender, 5000.into());
+		ext_update(|e| e.sender(spender));
+		// balances, allowance flags, acceptance mode and allowance, the recipient blocklist and opt-out,
+		// transfer unit, holding period, lock, retained minimum and per-block limit, then flags and
+		// allowance on spending, the reward accumulator for both accounts, holders list lookup,
+		// the holding period, the transfer counter and per-block limit
+		assert_eq!(storage_counter::measure(|| { contract.transferFrom(owner, samAddress, 1000.into()); }), (20, 4));
+	}
+
+	#[test]
+	fn approve_should_not_exceed_storage_ops_baseline() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.approve(spender, 1000.into());
+		// the maximum and minimum approval, the previous allowance and the owner's position
+		// in the spender's list, then the allowance and its flags
+		assert_eq!(storage_counter::measure(|| { contract.approve(spender, 5000.into()); }), (4, 2));
+	}
+
+	#[test]
+	fn reconcile_should_hold_after_transfers_and_burns() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 3000.into());
+		contract.transfer(bobAddress, 2000.into());
+		contract.burnBatch(vec![samAddress, bobAddress], vec![1000.into(), 500.into()]);
+		assert_eq!(contract.totalBurned(), 1500.into());
+		assert_eq!(contract.reconcile(), true);
+	}
+
+	#[test]
+	fn reconcile_should_detect_inconsistent_balances() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 3000.into());
+		// Credit tokens out of thin air
+		storage_write(&balance_key(&samAddress), &U256::from(3001).into());
+		assert_eq!(contract.reconcile(), false);
+	}
+
+	#[test]
+	fn accepted_allowance_should_be_spendable() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(40000.into());
+		assert_eq!(contract.setAllowanceAcceptance(true), true);
+		assert_eq!(contract.allowanceStatus(owner, spender), 0);
+		contract.approve(spender, 10000.into());
+		assert_eq!(contract.allowanceStatus(owner, spender), 1);
+
+		ext_update(|e| e.sender(spender));
+
+		assert_eq!(contract.acceptAllowance(owner), true);
+		assert_eq!(contract.allowanceStatus(owner, spender), 2);
+		assert_eq!(contract.transferFrom(owner, samAddress, 5000.into()), true);
+		assert_eq!(contract.balanceOf(samAddress), 5000.into());
+	}
+
+	#[test]
+	fn proposed_allowance_should_not_be_spendable() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(40000.into());
+		contract.setAllowanceAcceptance(true);
+		contract.approve(spender, 10000.into());
+
+		ext_update(|e| e.sender(spender));
+
+		assert_eq!(contract.transferFrom(owner, samAddress, 5000.into()), false);
+		assert_eq!(contract.balanceOf(samAddress), 0.into());
+		// Nothing to accept from an account which didn't approve
+		assert_eq!(contract.acceptAllowance(samAddress), false);
+		// Only the owner switches the mode
+		assert_eq!(contract.setAllowanceAcceptance(false), false);
+		assert_eq!(contract.allowanceAcceptanceRequired(), true);
+	}
+
+	#[test]
+	fn internalTransfer_should_move_balances_without_events() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		assert_eq!(contract.internalTransfer(samAddress, bobAddress, 400.into()), true);
+		assert_eq!(contract.balanceOf(samAddress), 600.into());
+		assert_eq!(contract.balanceOf(bobAddress), 400.into());
+		assert_eq!(ext_get().logs().len(), 1, "Only the initial transfer should be logged");
+
+		ext_update(|e| e.sender(samAddress));
+		assert_eq!(contract.internalTransfer(samAddress, bobAddress, 400.into()), false);
+		assert_eq!(contract.balanceOf(bobAddress), 400.into());
+	}
+
+	#[test]
+	fn transferCount_should_count_outgoing_transfers() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.approve(spender, 1000.into());
+		contract.transfer(samAddress, 100.into());
+		contract.transfer(samAddress, 100.into());
+		// Failed transfers aren't counted
+		contract.transfer(samAddress, 100000.into());
+
+		ext_update(|e| e.sender(spender));
+		contract.transferFrom(owner, samAddress, 100.into());
+
+		assert_eq!(contract.transferCount(owner), 3.into());
+		assert_eq!(contract.transferCount(samAddress), 0.into());
+	}
+
+	#[test]
+	fn approve_should_reject_approving_self() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		contract.constructor(40000.into());
+		assert_eq!(contract.approve(owner, 1000.into()), false);
+		assert_eq!(contract.approvePeriodic(owner, 1000.into(), 3600.into()), false);
+		assert_eq!(contract.allowance(owner, owner), 0.into());
+		assert_eq!(ext_get().logs().len(), 0, "Should be no events created");
+	}
+
+	#[test]
+	fn compute_create2_address_should_match_eip1014_examples() {
+		let mut keccak = Keccak::new_keccak256();
+		let mut init_code_hash = H256::zero();
+		keccak.update(&[0x00]);
+		keccak.finalize(&mut init_code_hash[..]);
+		assert_eq!(compute_create2_address(Address::zero(), H256::zero(), init_code_hash),
+			addr("4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38"));
+		assert_eq!(compute_create2_address(addr("deadbeef00000000000000000000000000000000"), H256::zero(), init_code_hash),
+			addr("b928f69bb1d91cd65274e3c79d8986362984fda3"));
+	}
+
+	#[test]
+	fn every_transfer_path_should_pass_the_conservation_check() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		let treasury = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		ext_reset(|e| e.sender(owner).address(treasury));
+		let mut contract = TokenContractInstance{};
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		// Every balance change goes through `assert_conserved`, which panics on a mismatch
+		assert_eq!(contract.transferSplit(samAddress, treasury, 1000.into(), 4000.into()), true);
+		assert_eq!(contract.distributeTreasury(vec![samAddress, spender], vec![1.into(), 3.into()]), true);
+		contract.approve(spender, 1000.into());
+		ext_update(|e| e.sender(spender));
+		assert_eq!(contract.transferFromWithFee(owner, samAddress, 500.into(), 50.into(), treasury), true);
+		ext_update(|e| e.sender(owner));
+		contract.setSelfTransferMode(SELF_TRANSFER_BURN);
+		assert_eq!(contract.transfer(treasury, 100.into()), true);
+		assert_eq!(contract.balanceOf(treasury), 50.into());
+		// Paths changing the supply go through `assert_supply_follows` as well
+		assert_eq!(contract.burnBatch(vec![samAddress, spender, samAddress], vec![100.into(), 50.into(), 100.into()]), true);
+		assert_eq!(contract.adminAdjustBalance(spender, 400.into()), true);
+		assert_eq!(contract.adminAdjustBalance(samAddress, 0.into()), true);
+		assert_eq!(contract.sumHolderBalances(), contract.totalSupply());
+		assert_eq!(contract.reconcile(), true);
+	}
+
+	#[test]
+	fn transfers_to_blocked_recipient_should_fail() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.approve(spender, 1000.into());
+		contract.transfer(samAddress, 1000.into());
+		assert_eq!(contract.blockRecipient(samAddress, true), true);
+		assert_eq!(contract.isRecipientBlocked(samAddress), true);
+		assert_eq!(contract.transfer(samAddress, 1000.into()), false);
+
+		ext_update(|e| e.sender(spender));
+		assert_eq!(contract.transferFrom(owner, samAddress, 1000.into()), false);
+		// The blocked recipient can still send tokens
+		ext_update(|e| e.sender(samAddress));
+		assert_eq!(contract.transfer(spender, 500.into()), true);
+		assert_eq!(contract.blockRecipient(samAddress, false), false);
+
+		ext_update(|e| e.sender(owner));
+		assert_eq!(contract.blockRecipient(samAddress, false), true);
+		assert_eq!(contract.transfer(samAddress, 1000.into()), true);
+		assert_eq!(contract.balanceOf(samAddress), 1500.into());
+	}
+
+	#[test]
+	fn initParams_should_return_constructor_arguments() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(12345.into());
+		contract.burnBatch(vec![owner], vec![45.into()]);
+		contract.transfer(samAddress, 100.into());
+		assert_eq!(contract.totalSupply(), 12300.into());
+		assert_eq!(contract.initParams(), 12345.into());
+	}
+
+	#[test]
+	fn transferFrom_should_respect_per_transaction_limit() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(40000.into());
+		assert_eq!(contract.approveWithTxLimit(spender, 10000.into(), 3000.into()), true);
+		assert_eq!(contract.txLimit(owner, spender), 3000.into());
+
+		ext_update(|e| e.sender(spender));
+
+		assert_eq!(contract.transferFrom(owner, samAddress, 3000.into()), true);
+		// The remaining allowance is sufficient, but it's more than a single transfer may move
+		assert_eq!(contract.transferFrom(owner, samAddress, 3001.into()), false);
+		assert_eq!(contract.balanceOf(samAddress), 3000.into());
+		assert_eq!(contract.allowance(owner, spender), 7000.into());
+
+		// A plain approval drops the limit
+		ext_update(|e| e.sender(owner));
+		contract.approve(spender, 10000.into());
+		assert_eq!(contract.txLimit(owner, spender), 0.into());
+	}
+
+	#[test]
+	fn isAuthorizedFor_should_authorize_only_the_owner() {
+		let mut contract = TokenContractInstance{};
+		let owner_address = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let samAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		ext_reset(|e| e.sender(owner_address.clone()));
+		contract.constructor(10000.into());
+		for action in 0..9 {
+			assert_eq!(contract.isAuthorizedFor(owner_address, action), true);
+			assert_eq!(contract.isAuthorizedFor(samAddress, action), false);
+		}
+		assert_eq!(contract.isAuthorizedFor(owner_address, 9), false);
+	}
+
+	#[test]
+	fn transferSplit_should_split_between_recipients() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		assert_eq!(contract.transferSplit(samAddress, bobAddress, 1001.into(), 3000.into()), true);
+		// The secondary share is rounded down
+		assert_eq!(contract.balanceOf(samAddress), 701.into());
+		assert_eq!(contract.balanceOf(bobAddress), 300.into());
+		assert_eq!(contract.balanceOf(owner), 8999.into());
+		assert_eq!(ext_get().logs().len(), 2, "Should be a Transfer event per recipient");
+	}
+
+	#[test]
+	fn transferSplit_should_reject_more_than_10000_bps() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		assert_eq!(contract.transferSplit(samAddress, bobAddress, 1000.into(), 10001.into()), false);
+		assert_eq!(contract.balanceOf(samAddress), 0.into());
+		assert_eq!(contract.balanceOf(bobAddress), 0.into());
+		assert_eq!(ext_get().logs().len(), 0, "Should be no events created");
+	}
+
+	#[test]
+	fn decaying_allowance_should_shrink_over_time() {
+		ext_reset(|e| e.timestamp(1000));
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(40000.into());
+		assert_eq!(contract.approveDecaying(spender, 1000.into(), 2.into()), true);
+
+		ext_update(|e| e.sender(spender).timestamp(1100));
+		assert_eq!(contract.allowance(owner, spender), 800.into());
+		assert_eq!(contract.transferFrom(owner, samAddress, 300.into()), true);
+		assert_eq!(contract.allowance(owner, spender), 500.into());
+
+		ext_update(|e| e.timestamp(1200));
+		assert_eq!(contract.allowance(owner, spender), 300.into());
+		assert_eq!(contract.transferFrom(owner, samAddress, 301.into()), false);
+		assert_eq!(contract.transferFrom(owner, samAddress, 300.into()), true);
+
+		// Fully decayed
+		ext_update(|e| e.timestamp(1201));
+		assert_eq!(contract.allowance(owner, spender), 0.into());
+		assert_eq!(contract.balanceOf(samAddress), 600.into());
+	}
+
+	#[test]
+	fn effectiveAllowance_should_apply_decay() {
+		ext_reset(|e| e.timestamp(1000));
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(40000.into());
+		contract.approveDecaying(spender, 1000.into(), 2.into());
+		ext_update(|e| e.timestamp(1100));
+		assert_eq!(contract.effectiveAllowance(owner, spender), 800.into());
+		// Fully decayed, i.e. expired
+		ext_update(|e| e.timestamp(1500));
+		assert_eq!(contract.effectiveAllowance(owner, spender), 0.into());
+	}
+
+	#[test]
+	fn effectiveAllowance_should_apply_acceptance_and_tx_limit() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(40000.into());
+		contract.setAllowanceAcceptance(true);
+		contract.approveWithTxLimit(spender, 10000.into(), 3000.into());
+		assert_eq!(contract.allowance(owner, spender), 10000.into());
+		assert_eq!(contract.effectiveAllowance(owner, spender), 0.into());
+
+		ext_update(|e| e.sender(spender));
+		contract.acceptAllowance(owner);
+		assert_eq!(contract.effectiveAllowance(owner, spender), 3000.into());
+	}
+
+	#[test]
+	fn distributeTreasury_should_split_evenly() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		let treasury = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		ext_reset(|e| e.sender(owner).address(treasury));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(treasury, 1000.into());
+		assert_eq!(contract.distributeTreasury(vec![samAddress, bobAddress], vec![1.into(), 1.into()]), true);
+		assert_eq!(contract.balanceOf(samAddress), 500.into());
+		assert_eq!(contract.balanceOf(bobAddress), 500.into());
+		assert_eq!(contract.balanceOf(treasury), 0.into());
+	}
+
+	#[test]
+	fn distributeTreasury_should_fail_for_recipient_that_cannot_receive() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		let treasury = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		ext_reset(|e| e.sender(owner).address(treasury));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(treasury, 1000.into());
+		contract.blockRecipient(bobAddress, true);
+		assert_eq!(contract.distributeTreasury(vec![samAddress, bobAddress], vec![1.into(), 1.into()]), false);
+		contract.blockRecipient(bobAddress, false);
+		contract.setTransferUnit(300.into());
+		assert_eq!(contract.distributeTreasury(vec![samAddress, bobAddress], vec![1.into(), 1.into()]), false);
+		assert_eq!(contract.balanceOf(samAddress), 0.into());
+		assert_eq!(contract.balanceOf(treasury), 1000.into());
+	}
+
+	#[test]
+	fn distributeTreasury_should_give_dust_to_last_recipient() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		let treasury = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		ext_reset(|e| e.sender(owner).address(treasury));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(treasury, 1000.into());
+		// Non-owners can't distribute
+		ext_update(|e| e.sender(samAddress));
+		assert_eq!(contract.distributeTreasury(vec![samAddress], vec![1.into()]), false);
+
+		ext_update(|e| e.sender(owner));
+		assert_eq!(contract.distributeTreasury(vec![samAddress, bobAddress], vec![1.into(), 2.into()]), true);
+		assert_eq!(contract.balanceOf(samAddress), 333.into());
+		assert_eq!(contract.balanceOf(bobAddress), 667.into());
+		assert_eq!(contract.balanceOf(treasury), 0.into());
+	}
+
+	#[test]
+	fn emergencyEvacuate_should_move_ether_and_tokens() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		let treasury = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		let safe = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		// The safe only accepts the contract's whole ether balance
+		ext_reset(|e| e.sender(owner).address(treasury).balance_of(treasury, 5000.into())
+			.endpoint(safe, Box::new(|val, _input: &[u8], _result: &mut [u8]| {
+				if val == U256::from(5000) { Ok(()) } else { Err("unexpected value") }
+			})));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.transfer(treasury, 1000.into());
+		ext_update(|e| e.sender(samAddress));
+		assert_eq!(contract.emergencyEvacuate(safe), false);
+
+		ext_update(|e| e.sender(owner));
+		assert_eq!(contract.emergencyEvacuate(treasury), false);
+		contract.blockRecipient(safe, true);
+		assert_eq!(contract.emergencyEvacuate(safe), false);
+		contract.blockRecipient(safe, false);
+		assert_eq!(contract.emergencyEvacuate(safe), true);
+		assert_eq!(contract.balanceOf(safe), 1000.into());
+		assert_eq!(contract.balanceOf(treasury), 0.into());
+		assert_eq!(ext_get().logs().len(), 2);
+	}
+
+	#[test]
+	fn transfers_beyond_block_limit_should_fail() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner).block_number(100));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.approve(spender, 1000.into());
+		assert_eq!(contract.setBlockTransferLimit(1.into()), true);
+
+		assert_eq!(contract.transfer(samAddress, 100.into()), true);
+		assert_eq!(contract.transfersThisBlock(owner), 1);
+		assert_eq!(contract.transfer(samAddress, 100.into()), false);
+		// Pulling the owner's tokens counts against the owner as well
+		ext_update(|e| e.sender(spender));
+		assert_eq!(contract.transferFrom(owner, samAddress, 100.into()), false);
+
+		ext_update(|e| e.block_number(101));
+		assert_eq!(contract.transfersThisBlock(owner), 0);
+		assert_eq!(contract.transferFrom(owner, samAddress, 100.into()), true);
+		assert_eq!(contract.balanceOf(samAddress), 200.into());
+	}
+
+	#[test]
+	fn received_tokens_should_be_held_for_holding_period() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner).timestamp(1000));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 100.into());
+		assert_eq!(contract.setHoldingPeriod(3600.into()), true);
+		contract.transfer(samAddress, 1000.into());
+		assert_eq!(contract.unlockTime(samAddress), 4600.into());
+
+		ext_update(|e| e.sender(samAddress).timestamp(4599));
+		// Tokens received before the holding period was set are free
+		assert_eq!(contract.transfer(bobAddress, 101.into()), false);
+		assert_eq!(contract.transfer(bobAddress, 100.into()), true);
+
+		ext_update(|e| e.timestamp(4600));
+		assert_eq!(contract.unlockTime(samAddress), 0.into());
+		assert_eq!(contract.transfer(bobAddress, 1000.into()), true);
+		assert_eq!(contract.balanceOf(bobAddress), 1100.into());
+	}
+
+	#[test]
+	fn dust_from_third_party_should_not_extend_holding_period() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner).timestamp(1000));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(bobAddress, 100.into());
+		contract.setHoldingPeriod(3600.into());
+		contract.transfer(samAddress, 1000.into());
+
+		ext_update(|e| e.sender(bobAddress).timestamp(4599));
+		assert_eq!(contract.transfer(samAddress, 1.into()), true);
+		assert_eq!(contract.unlockTime(samAddress), 4600.into());
+		ext_update(|e| e.timestamp(4600));
+		assert_eq!(contract.transfer(samAddress, 1.into()), true);
+		assert_eq!(contract.unlockTime(samAddress), 8199.into());
+
+		// Only the dust is still held back
+		ext_update(|e| e.sender(samAddress).timestamp(4601));
+		assert_eq!(contract.availableBalance(samAddress), 1000.into());
+		assert_eq!(contract.transfer(owner, 1000.into()), true);
+	}
+
+	#[test]
+	fn isInitialized_should_report_whether_constructor_ran() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		assert_eq!(contract.isInitialized(), false);
+		contract.constructor(10000.into());
+		assert_eq!(contract.isInitialized(), true);
+		// A second initialization is ignored
+		contract.constructor(50000.into());
+		assert_eq!(contract.totalSupply(), 10000.into());
+	}
+
+	#[test]
+	fn spendCapacity_should_be_limited_by_balance() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(5000.into());
+		contract.approve(spender, 8000.into());
+		assert_eq!(contract.spendCapacity(owner, spender), 5000.into());
+	}
+
+	#[test]
+	fn spendCapacity_should_be_limited_by_allowance() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(5000.into());
+		contract.approve(spender, 3000.into());
+		assert_eq!(contract.spendCapacity(owner, spender), 3000.into());
+	}
+
+	#[test]
+	fn verifyOwnerBalance_should_check_the_owner_balance() {
+		let mut contract = TokenContractInstance{};
+		let owner_address = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let samAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		ext_reset(|e| e.sender(owner_address.clone()));
+		contract.constructor(10000.into());
+		assert_eq!(contract.owner(), owner_address);
+		contract.transfer(samAddress, 2500.into());
+		assert_eq!(contract.verifyOwnerBalance(7500.into()), true);
+		assert_eq!(contract.verifyOwnerBalance(10000.into()), false);
+	}
+
+	#[test]
+	fn rewards_should_accrue_in_proportion_to_holdings() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		let pool = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		ext_reset(|e| e.sender(owner).address(pool));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(12000.into());
+		contract.transfer(samAddress, 4000.into());
+		contract.transfer(bobAddress, 4000.into());
+
+		// 10000 tokens earn rewards: the owner's remaining 2000, Sam's and Bob's
+		assert_eq!(contract.depositReward(2000.into()), true);
+		assert_eq!(contract.earned(owner), 400.into());
+		assert_eq!(contract.earned(samAddress), 800.into());
+		assert_eq!(contract.earned(bobAddress), 800.into());
+
+		ext_update(|e| e.sender(samAddress));
+		contract.transfer(bobAddress, 2000.into());
+
+		// Now Sam holds 2000 and Bob 6000 of 8000 eligible tokens
+		ext_update(|e| e.sender(owner));
+		assert_eq!(contract.depositReward(2000.into()), true);
+		assert_eq!(contract.earned(owner), 400.into());
+		assert_eq!(contract.earned(samAddress), 1300.into());
+		assert_eq!(contract.earned(bobAddress), 2300.into());
+		assert_eq!(contract.earned(pool), 0.into());
+
+		ext_update(|e| e.sender(samAddress));
+		assert_eq!(contract.claimReward(), 1300.into());
+		assert_eq!(contract.balanceOf(samAddress), 3300.into());
+		assert_eq!(contract.earned(samAddress), 0.into());
+		assert_eq!(contract.claimReward(), 0.into());
+		assert_eq!(contract.balanceOf(pool), 2700.into());
+	}
+
+	#[test]
+	fn depositReward_should_be_owner_only() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 4000.into());
+		ext_update(|e| e.sender(samAddress));
+		assert_eq!(contract.depositReward(1000.into()), false);
+		assert_eq!(contract.balanceOf(samAddress), 4000.into());
+	}
+
+	#[test]
+	fn approve_should_reject_approvals_below_minimum() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(40000.into());
+		assert_eq!(contract.setMinApproval(100.into()), true);
+		assert_eq!(contract.minApproval(), 100.into());
+
+		assert_eq!(contract.approve(spender, 99.into()), false);
+		assert_eq!(contract.approveDecaying(spender, 99.into(), 1.into()), false);
+		assert_eq!(contract.allowance(owner, spender), 0.into());
+		assert_eq!(contract.approve(spender, 100.into()), true);
+		assert_eq!(contract.allowance(owner, spender), 100.into());
+		assert_eq!(contract.approve(spender, 101.into()), true);
+		assert_eq!(contract.allowance(owner, spender), 101.into());
+		assert_eq!(contract.approve(spender, 0.into()), true);
+		assert_eq!(contract.allowance(owner, spender), 0.into());
+	}
+
+	#[test]
+	fn balance_key_should_not_collide_for_edge_addresses() {
+		let addresses = [
+			Address::zero(),
+			Address::from([0xff; 20]),
+			addr("0100000000000000000000000000000000000000"),
+			addr("0000000000000000000000000000000000000001"),
+			addr("ea674fdde714fd979de3edf0f56aa9716b898ec8"),
+			addr("eb674fdde714fd979de3edf0f56aa9716b898ec8"),
+			addr("ff674fdde714fd979de3edf0f56aa9716b898ec8"),
+		];
+		let mut keys: Vec<H256> = addresses.iter().map(|address| balance_key(address)).collect();
+		// The address is padded into the low 20 bytes, the namespace byte can't touch it
+		for (address, key) in addresses.iter().zip(keys.iter()) {
+			assert_eq!(key[0], 1);
+			assert_eq!(&key[12..], address.as_ref());
+		}
+		keys.extend_from_slice(&[total_supply_key(), owner_key(), holders_count_key(), original_supply_key(),
+			total_burned_key(), allowance_acceptance_key(), block_transfer_limit_key(), holding_period_key(),
+			initialized_key(), reward_per_token_key(), reward_pool_key(), min_approval_key(),
+			transfer_unit_key(), supply_floor_key(), multicall_lock_key(), max_allowance_key(),
+			self_transfer_mode_key(), adjusted_up_key(), adjusted_down_key()]);
+		let count = keys.len();
+		keys.sort();
+		keys.dedup();
+		assert_eq!(keys.len(), count, "storage keys should be distinct");
+	}
+
+	#[test]
+	fn renounced_owner_should_lose_all_privileges() {
+		let mut contract = TokenContractInstance{};
+		let owner_address = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let samAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		ext_reset(|e| e.sender(owner_address.clone()));
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		assert_eq!(contract.renounceOwnership(), true);
+		assert_eq!(contract.owner(), Address::zero());
+		assert_eq!(ext_get().logs().len(), 2);
+		assert_eq!(ext_get().logs()[1].topics.as_ref(), &[
+			OWNERSHIP_TRANSFERRED_TOPIC.parse().unwrap(), // hash of the event name
+			"000000000000000000000000ea674fdde714fd979de3edf0f56aa9716b898ec8".parse().unwrap(), // previous owner
+			"0000000000000000000000000000000000000000000000000000000000000000".parse().unwrap()]); // zero address
+
+		assert_eq!(contract.burnBatch(vec![samAddress], vec![100.into()]), false);
+		assert_eq!(contract.blockRecipient(samAddress, true), false);
+		assert_eq!(contract.setMinApproval(100.into()), false);
+		assert_eq!(contract.renounceOwnership(), false);
+		assert_eq!(contract.balanceOf(samAddress), 1000.into());
+		for action in 0..9 {
+			assert_eq!(contract.isAuthorizedFor(Address::zero(), action), false);
+			assert_eq!(contract.isAuthorizedFor(owner_address, action), false);
+		}
+		ext_update(|e| e.sender(Address::zero()));
+		assert_eq!(contract.setMinApproval(100.into()), false);
+	}
+
+	#[test]
+	fn should_only_transfer_multiples_of_the_unit() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		assert_eq!(contract.setTransferUnit(100.into()), true);
+		assert_eq!(contract.transferUnit(), 100.into());
+		assert_eq!(contract.transfer(samAddress, 150.into()), false);
+		assert_eq!(contract.transfer(samAddress, 200.into()), true);
+		contract.approve(spender, 5000.into());
+		ext_update(|e| e.sender(spender));
+		assert_eq!(contract.setTransferUnit(1.into()), false);
+		assert_eq!(contract.transferFrom(owner, samAddress, 150.into()), false);
+		assert_eq!(contract.transferFrom(owner, samAddress, 200.into()), true);
+		assert_eq!(contract.balanceOf(samAddress), 400.into());
+	}
+
+	#[test]
+	fn transferSplit_should_only_credit_multiples_of_the_unit() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let tomAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.setTransferUnit(100.into());
+		assert_eq!(contract.transferSplit(samAddress, tomAddress, 150.into(), 0.into()), false);
+		// 200 is whole, but a 25% share of it isn't
+		assert_eq!(contract.transferSplit(samAddress, tomAddress, 200.into(), 2500.into()), false);
+		assert_eq!(contract.transferSplit(samAddress, tomAddress, 400.into(), 2500.into()), true);
+		assert_eq!(contract.balanceOf(samAddress), 300.into());
+		assert_eq!(contract.balanceOf(tomAddress), 100.into());
+	}
+
+	#[test]
+	fn transferFromWithFee_should_only_credit_multiples_of_the_unit() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let collector = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		contract.constructor(10000.into());
+		contract.setTransferUnit(100.into());
+		contract.approve(spender, 5000.into());
+		ext_update(|e| e.sender(spender));
+		assert_eq!(contract.transferFromWithFee(owner, samAddress, 150.into(), 0.into(), collector), false);
+		assert_eq!(contract.transferFromWithFee(owner, samAddress, 150.into(), 50.into(), collector), false);
+		assert_eq!(contract.transferFromWithFee(owner, samAddress, 100.into(), 50.into(), collector), false);
+		assert_eq!(contract.transferFromWithFee(owner, samAddress, 100.into(), 100.into(), collector), true);
+		assert_eq!(contract.balanceOf(samAddress), 100.into());
+		assert_eq!(contract.balanceOf(collector), 100.into());
+	}
+
+	#[test]
+	fn anyone_should_read_raw_storage() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		assert_eq!(contract.rawStorage(balance_key(&samAddress)), H256::from(U256::from(1000)));
+		assert_eq!(contract.rawStorage(total_supply_key()), H256::from(U256::from(10000)));
+		ext_update(|e| e.sender(samAddress));
+		assert_eq!(contract.rawStorage(balance_key(&samAddress)), H256::from(U256::from(1000)));
+		assert_eq!(contract.rawStorage(balance_key(&addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1"))), H256::zero());
+	}
+
+	#[test]
+	fn positionOf_should_report_balance_held_tokens_and_rewards() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		let pool = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		ext_reset(|e| e.sender(owner).address(pool).timestamp(1000));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(12000.into());
+		contract.setHoldingPeriod(3600.into());
+		contract.transfer(samAddress, 4000.into());
+		// 10000 tokens earn rewards: the owner's remaining 6000 and Sam's
+		contract.depositReward(2000.into());
+		assert_eq!(contract.positionOf(samAddress), (4000.into(), 4000.into(), 800.into()));
+		assert_eq!(contract.positionOf(bobAddress), (0.into(), 0.into(), 0.into()));
+
+		ext_update(|e| e.timestamp(4600));
+		assert_eq!(contract.positionOf(samAddress), (4000.into(), 0.into(), 800.into()));
+	}
+
+	#[test]
+	fn one_shot_allowance_should_be_revoked_by_first_spend() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		assert_eq!(contract.approveOnce(spender, 1000.into()), true);
+		assert_eq!(contract.isOneShot(owner, spender), true);
+		ext_update(|e| e.sender(spender));
+		assert_eq!(contract.transferFrom(owner, samAddress, 400.into()), true);
+		assert_eq!(contract.allowance(owner, spender), 0.into());
+		assert_eq!(contract.transferFrom(owner, samAddress, 400.into()), false);
+		assert_eq!(contract.balanceOf(samAddress), 400.into());
+
+		// A plain approval is reusable again
+		ext_update(|e| e.sender(owner));
+		contract.approve(spender, 1000.into());
+		assert_eq!(contract.isOneShot(owner, spender), false);
+	}
+
+	#[test]
+	fn should_not_transfer_to_account_that_disabled_receiving() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		assert_eq!(contract.receiveEnabled(samAddress), true);
+		ext_update(|e| e.sender(samAddress));
+		contract.setReceiveEnabled(false);
+		assert_eq!(contract.receiveEnabled(samAddress), false);
+		ext_update(|e| e.sender(owner));
+		assert_eq!(contract.transfer(samAddress, 1000.into()), false);
+
+		ext_update(|e| e.sender(samAddress));
+		contract.setReceiveEnabled(true);
+		ext_update(|e| e.sender(owner));
+		assert_eq!(contract.transfer(samAddress, 1000.into()), true);
+		assert_eq!(contract.balanceOf(samAddress), 1000.into());
+	}
+
+	#[test]
+	fn transferFromBatch_should_pull_from_all_owners() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let aggregator = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		contract.approve(aggregator, 500.into());
+		ext_update(|e| e.sender(samAddress));
+		contract.approve(aggregator, 300.into());
+		ext_update(|e| e.sender(aggregator));
+		assert_eq!(contract.transferFromBatch(vec![owner, samAddress], bobAddress, vec![500.into(), 200.into()]), true);
+		assert_eq!(contract.balanceOf(bobAddress), 700.into());
+		assert_eq!(contract.allowance(samAddress, aggregator), 100.into());
+		assert_eq!(ext_get().logs().len(), 5);
+	}
+
+	#[test]
+	fn transferFromBatch_should_reject_batch_if_one_entry_fails() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let aggregator = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		contract.approve(aggregator, 500.into());
+		ext_update(|e| e.sender(samAddress));
+		contract.approve(aggregator, 100.into());
+		ext_update(|e| e.sender(aggregator));
+		assert_eq!(contract.transferFromBatch(vec![owner, samAddress], bobAddress, vec![500.into(), 200.into()]), false);
+		assert_eq!(contract.transferFromBatch(vec![owner, owner], bobAddress, vec![200.into(), 200.into()]), false);
+		assert_eq!(contract.transferFromBatch(vec![owner], bobAddress, vec![200.into(), 200.into()]), false);
+		assert_eq!(contract.balanceOf(bobAddress), 0.into());
+		assert_eq!(contract.allowance(owner, aggregator), 500.into());
+	}
+
+	#[test]
+	fn allowanceOccupied_should_be_true_after_approval() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		assert_eq!(contract.allowanceOccupied(owner, spender), false);
+		contract.approve(spender, 1000.into());
+		assert_eq!(contract.allowanceOccupied(owner, spender), true);
+		contract.approve(spender, 0.into());
+		assert_eq!(contract.allowanceOccupied(owner, spender), false);
+	}
+
+	#[test]
+	fn burnBatch_should_not_cross_supply_floor() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		assert_eq!(contract.setSupplyFloor(8000.into()), true);
+		assert_eq!(contract.supplyFloor(), 8000.into());
+		// Sam only has 1000, so the batch burns 2000 in total, once the cap is applied
+		assert_eq!(contract.burnBatch(vec![owner, samAddress, owner], vec![500.into(), 1500.into(), 501.into()]), false);
+		assert_eq!(contract.totalSupply(), 10000.into());
+		assert_eq!(contract.burnBatch(vec![owner, samAddress, owner], vec![500.into(), 1500.into(), 500.into()]), true);
+		assert_eq!(contract.totalSupply(), 8000.into());
+		assert_eq!(contract.balanceOf(owner), 8000.into());
+		assert_eq!(contract.balanceOf(samAddress), 0.into());
+		assert_eq!(contract.burnBatch(vec![owner], vec![1.into()]), false);
+	}
+
+	#[test]
+	fn pruneHolder_should_remove_zeroed_holder() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let aliceAddress = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		contract.transfer(bobAddress, 1000.into());
+		contract.transfer(aliceAddress, 1000.into());
+		assert_eq!(contract.pruneHolder(samAddress), false);
+
+		ext_update(|e| e.sender(samAddress));
+		contract.transfer(bobAddress, 1000.into());
+		assert_eq!(contract.pruneHolder(samAddress), true);
+		assert_eq!(contract.allHolders(), vec![owner, aliceAddress, bobAddress]);
+		assert_eq!(contract.pruneHolder(samAddress), false);
+		assert_eq!(contract.reconcile(), true);
+
+		// Alice took Sam's place, so she has to be found there
+		ext_update(|e| e.sender(aliceAddress));
+		contract.transfer(bobAddress, 1000.into());
+		assert_eq!(contract.pruneHolder(aliceAddress), true);
+		assert_eq!(contract.allHolders(), vec![owner, bobAddress]);
+
+		ext_update(|e| e.sender(bobAddress));
+		contract.transfer(samAddress, 500.into());
+		assert_eq!(contract.allHolders(), vec![owner, bobAddress, samAddress]);
+	}
+
+	#[test]
+	fn exempt_account_should_not_be_block_limited() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner).block_number(100));
+		let mut contract = TokenContractInstance{};
+		let marketMaker = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let samAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(marketMaker, 1000.into());
+		assert_eq!(contract.setBlockTransferLimit(1.into()), true);
+		assert_eq!(contract.setBlockLimitExempt(marketMaker, true), true);
+		assert_eq!(contract.isBlockLimitExempt(marketMaker), true);
+		assert_eq!(contract.transfer(samAddress, 100.into()), true);
+		assert_eq!(contract.transfer(samAddress, 100.into()), false);
+
+		ext_update(|e| e.sender(marketMaker));
+		assert_eq!(contract.transfer(samAddress, 100.into()), true);
+		assert_eq!(contract.transfer(samAddress, 100.into()), true);
+		assert_eq!(contract.transfer(samAddress, 100.into()), true);
+		assert_eq!(contract.setBlockLimitExempt(marketMaker, false), false);
+
+		ext_update(|e| e.sender(owner));
+		assert_eq!(contract.setBlockLimitExempt(marketMaker, false), true);
+		ext_update(|e| e.sender(marketMaker));
+		assert_eq!(contract.transfer(samAddress, 100.into()), false);
+	}
+
+	#[test]
+	fn erc20Selectors_should_match_known_selectors() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		assert_eq!(contract.erc20Selectors(), vec![0x70a08231, 0x18160ddd, 0xa9059cbb, 0x23b872dd, 0x095ea7b3, 0xdd62ed3e]);
+	}
+
+	#[test]
+	fn token_amount_should_scale_whole_units() {
+		assert_eq!(TokenAmount::from_units(5, 0).unwrap().as_raw(), 5.into());
+		assert_eq!(TokenAmount::from_units(5, 6).unwrap().as_raw(), 5_000_000.into());
+		assert_eq!(TokenAmount::from_units(5, 18).unwrap().as_raw(), U256::from(5_000_000_000_000_000_000u64));
+		assert_eq!(TokenAmount::from(U256::from(1000)).as_raw(), 1000.into());
+	}
+
+	#[test]
+	fn token_amount_should_not_overflow() {
+		assert_eq!(TokenAmount::from_units(1, 77).unwrap().as_raw(), U256::from(10).pow(77.into()));
+		assert_eq!(TokenAmount::from_units(1, 78), None);
+		assert_eq!(TokenAmount::from_units(u64::max_value(), 60), None);
+		assert_eq!(TokenAmount::from_units(0, 255).unwrap().as_raw(), 0.into());
+	}
 
-impl TokenContract for TokenContractInstance {
-	fn constructor(&mut self, total_supply: U256) {
-		let sender = eth::sender();
-		// Set up the total supply for the token
-		eth::write(&total_supply_key(), &total_supply.into());
-		// Give all tokens to the contract owner
-		eth::write(&balance_key(&sender), &total_supply.into());
-		// Set the contract owner
-		eth::write(&owner_key(), &H256::from(sender).into());
+	#[test]
+	fn ownersApproving_should_list_owners_who_approved_the_spender() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.approve(spender, 0.into());
+		assert_eq!(contract.ownersApproving(spender), Vec::<Address>::new());
+		ext_update(|e| e.sender(samAddress));
+		contract.approve(spender, 300.into());
+		ext_update(|e| e.sender(owner));
+		contract.approve(spender, 1000.into());
+		contract.approve(spender, 500.into());
+		assert_eq!(contract.ownersApproving(spender), vec![samAddress, owner]);
+		assert_eq!(contract.allowancesOfSpender(spender), vec![U256::from(300), U256::from(500)]);
 	}
 
-	fn balanceOf(&mut self, owner: Address) -> U256 {
-		read_balance_of(&owner)
+	#[test]
+	fn allowance_should_be_revoked_after_last_use() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		assert_eq!(contract.approveWithUses(spender, 1000.into(), 0), false);
+		assert_eq!(contract.approveWithUses(spender, 1000.into(), 2), true);
+		assert_eq!(contract.remainingUses(owner, spender), 2);
+		ext_update(|e| e.sender(spender));
+		assert_eq!(contract.transferFrom(owner, samAddress, 100.into()), true);
+		assert_eq!(contract.remainingUses(owner, spender), 1);
+		assert_eq!(contract.allowance(owner, spender), 900.into());
+		assert_eq!(contract.transferFrom(owner, samAddress, 100.into()), true);
+		assert_eq!(contract.remainingUses(owner, spender), 0);
+		assert_eq!(contract.allowance(owner, spender), 0.into());
+		assert_eq!(contract.transferFrom(owner, samAddress, 100.into()), false);
+		assert_eq!(contract.balanceOf(samAddress), 200.into());
 	}
 
-	fn totalSupply(&mut self) -> U256 {
-		U256::from_big_endian(&eth::read(&total_supply_key()))
+	#[test]
+	fn config_should_reflect_owner_settings() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		contract.constructor(10000.into());
+		assert_eq!(contract.config(), (false, 0.into(), 0.into(), 0.into(), 0.into(), 0.into(), 0.into(), 0));
+		contract.setAllowanceAcceptance(true);
+		contract.setBlockTransferLimit(2.into());
+		contract.setHoldingPeriod(3600.into());
+		contract.setMinApproval(10.into());
+		contract.setTransferUnit(100.into());
+		contract.setSupplyFloor(5000.into());
+		contract.setMaxAllowancePerSpender(2000.into());
+		contract.setSelfTransferMode(1);
+		assert_eq!(contract.config(), (true, 2.into(), 3600.into(), 10.into(), 100.into(), 5000.into(), 2000.into(), 1));
 	}
 
-	fn transfer(&mut self, to: Address, amount: U256) -> bool {
-		let sender = eth::sender();
-		let senderBalance = read_balance_of(&sender);
-		let recipientBalance = read_balance_of(&to);
-	if amount == 0.into() || senderBalance < amount || to == sender {
-			false
-		} else {
-			let new_sender_balance = senderBalance - amount;
-			let new_recipient_balance = recipientBalance + amount;
-			// TODO: impl From<U256> for H256 makes convertion to big endian. Could be optimized
-			eth::write(&balance_key(&sender), &new_sender_balance.into());
-			eth::write(&balance_key(&to), &new_recipient_balance.into());
-			self.Transfer(sender, to, amount);
-			true
+	// ABI-encodes a call: the selector of the signature followed by the arguments as 32-byte words
+	fn encode_call(signature: &str, args: &[H256]) -> Vec<u8> {
+		let id = selector(signature);
+		let mut call = vec![(id >> 24) as u8, (id >> 16) as u8, (id >> 8) as u8, id as u8];
+		for arg in args {
+			call.extend_from_slice(arg.as_ref());
 		}
+		call
 	}
 
-	fn approve(&mut self, spender: Address, value: U256) -> bool {
-		write_allowance(&allowance_key(&eth::sender(), &spender), value);
-		self.Approval(eth::sender(), spender, value);
-		true
+	#[test]
+	fn multicall_should_run_calls_in_sequence() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.approve(samAddress, 1000.into());
+		ext_update(|e| e.sender(samAddress));
+		let calls = vec![
+			encode_call("approve(address,uint256)", &[H256::from(bobAddress), H256::from(U256::from(300))]),
+			encode_call("transferFrom(address,address,uint256)",
+				&[H256::from(owner), H256::from(samAddress), H256::from(U256::from(500))]),
+			// Sam only has tokens to transfer once the transferFrom ran
+			encode_call("transfer(address,uint256)", &[H256::from(bobAddress), H256::from(U256::from(100))]),
+			encode_call("transfer(address,uint256)", &[H256::from(bobAddress), H256::from(U256::from(1000))]),
+		];
+		assert_eq!(contract.multicall(calls), vec![true, true, true, false]);
+		assert_eq!(contract.allowance(samAddress, bobAddress), 300.into());
+		assert_eq!(contract.balanceOf(samAddress), 400.into());
+		assert_eq!(contract.balanceOf(bobAddress), 100.into());
 	}
 
-	fn allowance(&mut self, owner: Address, spender: Address) -> U256 {
-		read_allowance(&allowance_key(&owner, &spender))
+	#[test]
+	fn multicall_should_reject_invalid_arguments() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		let mut dirty_address = H256::from(samAddress);
+		dirty_address[0] = 1;
+		let calls = vec![
+			encode_call("setReceiveEnabled(bool)", &[H256::from(U256::from(2))]),
+			encode_call("transfer(address,uint256)", &[dirty_address, H256::from(U256::from(100))]),
+			encode_call("setSelfTransferMode(uint8)", &[H256::from(U256::from(256 + 1))]),
+			encode_call("setReceiveEnabled(bool)", &[H256::from(U256::one())]),
+		];
+		assert_eq!(contract.multicall(calls), vec![false, false, false, true]);
+		assert_eq!(contract.balanceOf(samAddress), 0.into());
+		assert_eq!(contract.selfTransferMode(), SELF_TRANSFER_ACCEPT);
 	}
 
-	fn transferFrom(&mut self, from: Address, to: Address, amount: U256) -> bool {
-		let fromBalance = read_balance_of(&from);
-		let recipientBalance = read_balance_of(&to);
-		let a_key = allowance_key(&from, &eth::sender());
-		let allowed = read_allowance(&a_key);
-		if  allowed < amount || amount == 0.into() || fromBalance < amount  || to == from {
-			false
-		} else {
-			let new_allowed = allowed - amount;
-			let new_from_balance = fromBalance - amount;
-			let new_recipient_balance = recipientBalance + amount;
-			eth::write(&a_key, &new_allowed.into());
-			eth::write(&balance_key(&from), &new_from_balance.into());
-			eth::write(&balance_key(&to), &new_recipient_balance.into());
-			self.Transfer(from, to, amount);
-			true
-		}
+	#[test]
+	fn multicall_should_report_void_methods_as_successful() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		ext_update(|e| e.sender(samAddress));
+		let calls = vec![encode_call("setReceiveEnabled(bool)", &[H256::from(U256::zero())])];
+		assert_eq!(contract.multicall(calls), vec![true]);
+		assert_eq!(contract.receiveEnabled(samAddress), false);
 	}
-}
 
-#[cfg(test)]
-extern crate pwasm_test;
+	#[test]
+	fn multicall_should_reject_nested_and_malformed_calls() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		let transfer = encode_call("transfer(address,uint256)", &[H256::from(samAddress), H256::from(U256::from(100))]);
+		let nested = encode_call("multicall(bytes[])", &[H256::from(U256::from(32)), H256::from(U256::zero())]);
+		let calls = vec![
+			nested,
+			encode_call("unknown()", &[]),
+			vec![0xa9, 0x05],
+			transfer[..transfer.len() - 1].to_vec(),
+			// A view isn't run either
+			encode_call("totalSupply()", &[]),
+			transfer,
+		];
+		assert_eq!(contract.multicall(calls), vec![false, false, false, false, false, true]);
+		assert_eq!(contract.balanceOf(samAddress), 100.into());
+	}
 
-#[cfg(test)]
-#[allow(non_snake_case)]
-mod tests {
-	extern crate std;
-	use super::*;
-	use pwasm_test::{ext_reset, ext_update, ext_get, External};
+	#[test]
+	fn should_not_transfer_below_retained_minimum() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let validator = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(validator, 1000.into());
+		assert_eq!(contract.setMinRetained(validator, 600.into()), true);
+		assert_eq!(contract.minRetained(validator), 600.into());
 
-	fn addr(a: &'static str) -> Address {
-		a.parse().expect(&format!("parsing of '{}' failed", a))
+		ext_update(|e| e.sender(validator));
+		contract.approve(spender, 1000.into());
+		assert_eq!(contract.transfer(owner, 300.into()), true);
+		assert_eq!(contract.transfer(owner, 101.into()), false);
+		ext_update(|e| e.sender(spender));
+		assert_eq!(contract.transferFrom(validator, owner, 101.into()), false);
+		assert_eq!(contract.transferFrom(validator, owner, 100.into()), true);
+		assert_eq!(contract.balanceOf(validator), 600.into());
 	}
 
 	#[test]
-	fn balanceOf_should_return_balance() {
-		ext_reset(|e| e.storage([1,0,0,0,0,0,0,0,0,0,0,0,
-							31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31].into(), U256::from(100000).into()));
-		let address = Address::from([31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31,31]);
+	fn should_not_approve_above_max_allowance() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
 		let mut contract = TokenContractInstance{};
-		assert_eq!(contract.balanceOf(address), 100000.into());
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		assert_eq!(contract.approve(spender, 5000.into()), true);
+		assert_eq!(contract.setMaxAllowancePerSpender(1000.into()), true);
+		assert_eq!(contract.maxAllowancePerSpender(), 1000.into());
+		assert_eq!(contract.approve(spender, 1001.into()), false);
+		assert_eq!(contract.approveOnce(spender, 1001.into()), false);
+		assert_eq!(contract.approve(spender, 1000.into()), true);
+		assert_eq!(contract.allowance(owner, spender), 1000.into());
+
+		assert_eq!(contract.setMaxAllowancePerSpender(0.into()), true);
+		assert_eq!(contract.approve(spender, 5000.into()), true);
 	}
 
 	#[test]
-	fn totalSupply_should_return_total_supply_contract_was_initialized_with() {
+	fn allowanceCountOf_should_count_nonzero_allowances() {
 		ext_reset(|e| e);
 		let mut contract = TokenContractInstance{};
-		let total_supply = 42.into();
-		contract.constructor(total_supply);
-		assert_eq!(contract.totalSupply(), total_supply);
+		let owner: Address = Address::zero();
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.approve(spender, 1000.into());
+		contract.approve(spender, 2000.into());
+		contract.approveOnce(samAddress, 500.into());
+		assert_eq!(contract.allowanceCountOf(owner), 2);
+		contract.approve(spender, 0.into());
+		assert_eq!(contract.allowanceCountOf(owner), 1);
+
+		// Spending an allowance down to zero counts as well
+		ext_update(|e| e.sender(samAddress));
+		contract.transferFrom(owner, samAddress, 100.into());
+		assert_eq!(contract.allowanceCountOf(owner), 0);
 	}
 
 	#[test]
-	fn should_succeed_in_creating_max_possible_amount_of_tokens() {
-		ext_reset(|e| e);
+	fn locked_transfer_should_be_unavailable_until_release() {
+		ext_reset(|e| e.timestamp(1000));
 		let mut contract = TokenContractInstance{};
-		// set total supply to maximum value of an unsigned 256 bit integer
-		let total_supply = U256::from_dec_str("115792089237316195423570985008687907853269984665640564039457584007913129639935").unwrap();
-		assert_eq!(total_supply, U256::max_value());
-		contract.constructor(total_supply);
-		assert_eq!(contract.totalSupply(), total_supply);
+		let owner: Address = Address::zero();
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		assert_eq!(contract.transferAndLock(samAddress, 1000.into(), 5000.into()), true);
+		assert_eq!(contract.balanceOf(samAddress), 1000.into());
+		assert_eq!(contract.availableBalance(samAddress), 0.into());
+		assert_eq!(contract.positionOf(samAddress), (1000.into(), 1000.into(), 0.into()));
+
+		ext_update(|e| e.sender(samAddress).timestamp(4999));
+		assert_eq!(contract.transfer(owner, 1.into()), false);
+
+		ext_update(|e| e.timestamp(5000));
+		assert_eq!(contract.availableBalance(samAddress), 1000.into());
+		assert_eq!(contract.transfer(owner, 1000.into()), true);
 	}
 
 	#[test]
-	fn should_initially_give_the_total_supply_to_the_creator() {
-		ext_reset(|e| e);
+	fn later_lock_should_not_delay_earlier_grant() {
+		ext_reset(|e| e.timestamp(1000));
 		let mut contract = TokenContractInstance{};
-		let total_supply = 10000.into();
-		contract.constructor(total_supply);
-		assert_eq!(contract.balanceOf(ext_get().sender()), total_supply);
+		let owner: Address = Address::zero();
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(bobAddress, 1000.into());
+		assert_eq!(contract.transferAndLock(samAddress, 1000.into(), 5000.into()), true);
+		ext_update(|e| e.sender(bobAddress));
+		assert_eq!(contract.transferAndLock(samAddress, 1.into(), U256::max_value()), false);
+		assert_eq!(contract.transferAndLock(samAddress, 1.into(), 5000.into()), true);
+		assert_eq!(contract.balanceOf(samAddress), 1001.into());
+
+		ext_update(|e| e.sender(samAddress).timestamp(5000));
+		assert_eq!(contract.availableBalance(samAddress), 1001.into());
+		assert_eq!(contract.transfer(owner, 1001.into()), true);
 	}
 
 	#[test]
-	fn should_succeed_transfering_1000_from_owner_to_another_address() {
+	fn locked_transfer_should_not_be_held_as_well() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner).timestamp(1000));
 		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.setHoldingPeriod(100.into());
+		contract.transfer(samAddress, 100.into());
+		ext_update(|e| e.timestamp(1050));
+		assert_eq!(contract.transferAndLock(samAddress, 500.into(), 1060.into()), true);
+		assert_eq!(contract.availableBalance(samAddress), 0.into());
 
-		let owner_address = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
-		let sam_address = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		// Only the first 100 are still held, until 1100
+		ext_update(|e| e.sender(samAddress).timestamp(1070));
+		assert_eq!(contract.availableBalance(samAddress), 500.into());
+		assert_eq!(contract.transfer(owner, 500.into()), true);
+		ext_update(|e| e.timestamp(1100));
+		assert_eq!(contract.availableBalance(samAddress), 100.into());
+	}
 
-		ext_reset(|e| e.sender(owner_address.clone()));
+	#[test]
+	fn hasTransacted_should_be_set_by_first_receipt() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let owner: Address = Address::zero();
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		assert_eq!(contract.hasTransacted(owner), true);
+		assert_eq!(contract.hasTransacted(samAddress), false);
+		contract.transfer(samAddress, 1000.into());
+		assert_eq!(contract.hasTransacted(samAddress), true);
 
-		let total_supply = 10000.into();
-		contract.constructor(total_supply);
+		// Giving everything away and being pruned doesn't make the account new again
+		ext_update(|e| e.sender(samAddress));
+		contract.transfer(owner, 1000.into());
+		contract.pruneHolder(samAddress);
+		assert_eq!(contract.hasTransacted(samAddress), true);
+	}
 
-		assert_eq!(contract.balanceOf(owner_address), total_supply);
+	#[test]
+	fn batches_should_not_exceed_max_batch_size() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		contract.constructor(10000.into());
+		let size = contract.maxBatchSize() as usize;
+		assert_eq!(contract.burnBatch(vec![owner; size + 1], vec![1.into(); size + 1]), false);
+		assert_eq!(contract.totalSupply(), 10000.into());
+		assert_eq!(contract.burnBatch(vec![owner; size], vec![1.into(); size]), true);
+		assert_eq!(contract.totalSupply(), U256::from(10000 - size as u64));
+	}
 
-		assert_eq!(contract.transfer(sam_address, 1000.into()), true);
-		assert_eq!(ext_get().logs().len(), 1);
-		assert_eq!(ext_get().logs()[0].topics.as_ref(), &[
-			"ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef".parse().unwrap(), // hash of the event name
+	#[test]
+	fn sumHolderBalances_should_equal_total_supply() {
+		ext_reset(|e| e);
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let bobAddress = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		contract.transfer(bobAddress, 2500.into());
+		ext_update(|e| e.sender(samAddress));
+		contract.transfer(bobAddress, 400.into());
+		assert_eq!(contract.sumHolderBalances(), 10000.into());
+		assert_eq!(contract.sumHolderBalances(), contract.totalSupply());
+	}
+
+	#[test]
+	fn transferWithMemo_should_emit_memo_after_transfer() {
+		let mut contract = TokenContractInstance{};
+		let owner_address = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		let sam_address = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
+		let memo: H256 = "00000000000000000000000000000000000000000000000000000000494e5631".parse().unwrap();
+		ext_reset(|e| e.sender(owner_address.clone()));
+		contract.constructor(10000.into());
+		assert_eq!(contract.transferWithMemo(sam_address, 1000.into(), memo), true);
+		assert_eq!(ext_get().logs().len(), 2);
+		assert_eq!(ext_get().logs()[0].topics[0], TRANSFER_TOPIC.parse::<H256>().unwrap());
+		assert_eq!(ext_get().logs()[1].topics.as_ref(), &[
+			TRANSFER_MEMO_TOPIC.parse().unwrap(), // hash of the event name
 			"000000000000000000000000ea674fdde714fd979de3edf0f56aa9716b898ec8".parse().unwrap(), // sender address
 			"000000000000000000000000db6fd484cfa46eeeb73c71edee823e4812f9e2e1".parse().unwrap()]); // recipient address
-		assert_eq!(ext_get().logs()[0].data.as_ref(), &[
-			0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 232]);
-		assert_eq!(contract.balanceOf(owner_address), 9000.into());
+		assert_eq!(ext_get().logs()[1].data.as_ref(), memo.as_ref());
 		assert_eq!(contract.balanceOf(sam_address), 1000.into());
+
+		assert_eq!(contract.transferWithMemo(sam_address, 50000.into(), memo), false);
+		assert_eq!(ext_get().logs().len(), 2);
 	}
 
 	#[test]
-	fn should_return_false_transfer_not_sufficient_funds() {
-		ext_reset(|e| e);
+	fn transfer_to_contract_should_follow_self_transfer_mode() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		let contract_address = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		ext_reset(|e| e.sender(owner).address(contract_address));
 		let mut contract = TokenContractInstance{};
+		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
 		contract.constructor(10000.into());
-		assert_eq!(contract.transfer(addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1"), 50000.into()), false);
-		assert_eq!(contract.balanceOf(::pwasm_ethereum::sender()), 10000.into());
-		assert_eq!(contract.balanceOf(addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1")), 0.into());
-		assert_eq!(ext_get().logs().len(), 0, "Should be no events created");
+		contract.approve(spender, 5000.into());
+		assert_eq!(contract.selfTransferMode(), SELF_TRANSFER_ACCEPT);
+		assert_eq!(contract.transfer(contract_address, 100.into()), true);
+		assert_eq!(contract.balanceOf(contract_address), 100.into());
+
+		assert_eq!(contract.setSelfTransferMode(3), false);
+		assert_eq!(contract.setSelfTransferMode(SELF_TRANSFER_REJECT), true);
+		assert_eq!(contract.transfer(contract_address, 100.into()), false);
+		ext_update(|e| e.sender(spender));
+		assert_eq!(contract.transferFrom(owner, contract_address, 100.into()), false);
+		assert_eq!(contract.balanceOf(contract_address), 100.into());
+
+		ext_update(|e| e.sender(owner));
+		assert_eq!(contract.setSelfTransferMode(SELF_TRANSFER_BURN), true);
+		assert_eq!(contract.transfer(contract_address, 100.into()), true);
+		ext_update(|e| e.sender(spender));
+		assert_eq!(contract.transferFrom(owner, contract_address, 200.into()), true);
+		assert_eq!(contract.balanceOf(contract_address), 100.into());
+		assert_eq!(contract.totalSupply(), 9700.into());
+		assert_eq!(contract.totalBurned(), 300.into());
+		assert_eq!(contract.reconcile(), true);
 	}
 
 	#[test]
-	fn approve_should_approve() {
-		ext_reset(|e| e);
+	fn every_transfer_path_should_burn_tokens_sent_to_contract_in_burn_mode() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		let contract_address = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		ext_reset(|e| e.sender(owner).address(contract_address));
 		let mut contract = TokenContractInstance{};
 		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
-		contract.constructor(40000.into());
-		contract.approve(spender, 40000.into());
-		assert_eq!(ext_get().logs().len(), 1, "Should be 1 event logged");
-		assert_eq!(ext_get().logs()[0].topics.as_ref(), &[
-			"8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925".parse().unwrap(), // hash of the event name
-			"0000000000000000000000000000000000000000000000000000000000000000".parse().unwrap(), // sender (owner) address
-			"000000000000000000000000db6fd484cfa46eeeb73c71edee823e4812f9e2e1".parse().unwrap()]); // spender address
-		assert_eq!(contract.allowance(::pwasm_ethereum::sender(), spender.clone()), 40000.into());
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		contract.setSelfTransferMode(SELF_TRANSFER_BURN);
+		contract.setSupplyFloor(9000.into());
+		assert_eq!(contract.transferSplit(samAddress, contract_address, 400.into(), 5000.into()), true);
+		assert_eq!(contract.transferAndLock(contract_address, 100.into(), 2000.into()), false);
+		contract.approve(spender, 1000.into());
+		ext_update(|e| e.sender(samAddress));
+		contract.approve(spender, 1000.into());
+		ext_update(|e| e.sender(spender));
+		assert_eq!(contract.transferFromWithFee(owner, samAddress, 100.into(), 100.into(), contract_address), true);
+		// Each entry is within the floor, but not both together
+		assert_eq!(contract.transferFromBatch(vec![owner, samAddress], contract_address, vec![400.into(), 400.into()]), false);
+		assert_eq!(contract.transferFromBatch(vec![owner, samAddress], contract_address, vec![300.into(), 400.into()]), true);
+		assert_eq!(contract.balanceOf(contract_address), 0.into());
+		assert_eq!(contract.totalSupply(), 9000.into());
+		assert_eq!(contract.totalBurned(), 1000.into());
+		assert_eq!(contract.reconcile(), true);
 	}
 
 	#[test]
-	fn spender_should_be_able_to_spend_if_allowed() {
-		ext_reset(|e| e);
+	fn maxTransferableNow_should_apply_all_restrictions() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner).timestamp(1000).block_number(100));
 		let mut contract = TokenContractInstance{};
-		let owner: Address = Address::zero();
-		let spender: Address = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
-		let samAddress: Address = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
-		contract.constructor(40000.into());
-		contract.approve(spender, 10000.into());
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		assert_eq!(contract.maxTransferableNow(samAddress), 1000.into());
+		contract.transferAndLock(samAddress, 500.into(), 5000.into());
+		assert_eq!(contract.maxTransferableNow(samAddress), 1000.into());
+		contract.setMinRetained(samAddress, 750.into());
+		assert_eq!(contract.maxTransferableNow(samAddress), 750.into());
+		contract.setTransferUnit(100.into());
+		assert_eq!(contract.maxTransferableNow(samAddress), 700.into());
 
-		// Build different external with sender = spender
-		ext_update(|e| e.sender(spender));
+		contract.setBlockTransferLimit(1.into());
+		ext_update(|e| e.sender(samAddress));
+		assert_eq!(contract.transfer(owner, 700.into()), true);
+		assert_eq!(contract.maxTransferableNow(samAddress), 0.into());
+		// In the next block the lock is released too, 50 above the minimum are less than a unit
+		ext_update(|e| e.block_number(101).timestamp(5000));
+		assert_eq!(contract.maxTransferableNow(samAddress), 0.into());
+		ext_update(|e| e.sender(owner));
+		contract.setTransferUnit(1.into());
+		assert_eq!(contract.maxTransferableNow(samAddress), 50.into());
+	}
 
-		assert_eq!(contract.transferFrom(owner.clone(), samAddress.clone(), 5000.into()), true);
-		assert_eq!(contract.balanceOf(samAddress.clone()), 5000.into());
-		assert_eq!(contract.balanceOf(owner.clone()), 35000.into());
+	#[test]
+	fn adminAdjustBalance_should_change_total_supply_by_the_difference() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		ext_reset(|e| e.sender(owner));
+		let mut contract = TokenContractInstance{};
+		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
+		contract.constructor(10000.into());
+		contract.transfer(samAddress, 1000.into());
+		assert_eq!(contract.adminAdjustBalance(samAddress, 1500.into()), true);
+		assert_eq!(contract.balanceOf(samAddress), 1500.into());
+		assert_eq!(contract.totalSupply(), 10500.into());
+		assert_eq!(ext_get().logs().len(), 2);
+		assert_eq!(ext_get().logs()[1].topics.as_ref(), &[
+			ADMIN_ADJUSTMENT_TOPIC.parse().unwrap(), // hash of the event name
+			"000000000000000000000000ea674fdde714fd979de3edf0f56aa9716b898ec8".parse().unwrap()]); // account
+		let mut data = [0u8; 64];
+		U256::from(1000).to_big_endian(&mut data[..32]);
+		U256::from(1500).to_big_endian(&mut data[32..]);
+		assert_eq!(ext_get().logs()[1].data.as_ref(), &data[..]);
+		assert_eq!(contract.reconcile(), true);
 
-		assert_eq!(contract.transferFrom(owner.clone(), samAddress.clone(), 5000.into()), true);
-		assert_eq!(contract.balanceOf(samAddress.clone()), 10000.into());
-		assert_eq!(contract.balanceOf(owner.clone()), 30000.into());
+		assert_eq!(contract.adminAdjustBalance(samAddress, 200.into()), true);
+		assert_eq!(contract.totalSupply(), 9200.into());
+		assert_eq!(contract.sumHolderBalances(), contract.totalSupply());
+		assert_eq!(contract.reconcile(), true);
+		contract.burnBatch(vec![samAddress], vec![100.into()]);
+		assert_eq!(contract.reconcile(), true);
 
-		// The limit has reached. No more coins should be available to spend for the spender
-		assert_eq!(contract.transferFrom(owner.clone(), samAddress.clone(), 1.into()), false);
-		assert_eq!(contract.balanceOf(samAddress.clone()), 10000.into());
-		assert_eq!(contract.balanceOf(owner.clone()), 30000.into());
-		assert_eq!(ext_get().logs().len(), 3, "Two events should be created");
+		ext_update(|e| e.sender(samAddress));
+		assert_eq!(contract.adminAdjustBalance(samAddress, 5000.into()), false);
+		assert_eq!(contract.balanceOf(samAddress), 200.into());
 	}
 
 	#[test]
-	fn spender_should_not_be_able_to_spend_if_owner_has_no_coins() {
-		ext_reset(|e| e);
+	fn adminAdjustBalance_should_respect_floor_pool_and_restrictions() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		let pool = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		ext_reset(|e| e.sender(owner).address(pool).timestamp(1000));
 		let mut contract = TokenContractInstance{};
-		let owner: Address = Address::zero();
-		let spender = addr("db6fd484cfa46eeeb73c71edee823e4812f9e2e1");
 		let samAddress = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
-		contract.constructor(70000.into());
-		contract.transfer(samAddress, 30000.into());
-		contract.approve(spender, 40000.into());
+		contract.constructor(10000.into());
+		contract.transfer(pool, 100.into());
+		assert_eq!(contract.adminAdjustBalance(pool, 0.into()), false);
 
-		// Build different external with sender = spender
-		ext_update(|e| e.sender(spender));
+		contract.setSupplyFloor(9500.into());
+		contract.transferAndLock(samAddress, 1000.into(), 5000.into());
+		assert_eq!(contract.adminAdjustBalance(samAddress, 0.into()), false);
+		assert_eq!(contract.adminAdjustBalance(samAddress, 600.into()), true);
+		assert_eq!(contract.totalSupply(), 9600.into());
+		// The lock shrank with the balance, tokens received later are free
+		contract.transfer(samAddress, 500.into());
+		assert_eq!(contract.availableBalance(samAddress), 500.into());
+		assert_eq!(contract.reconcile(), true);
+	}
 
-		// Despite of the allowance, can't transfer because the owner is out of tokens
-		assert_eq!(contract.transferFrom(owner.clone(), samAddress.clone(), 40001.into()), false);
-		assert_eq!(contract.balanceOf(samAddress.clone()), 30000.into());
-		assert_eq!(contract.balanceOf(owner.clone()), 40000.into());
-		assert_eq!(ext_get().logs().len(), 2, "Should be no events created");
+	#[test]
+	fn depositReward_should_leave_state_untouched_without_eligible_holders() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		let pool = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		ext_reset(|e| e.sender(owner).address(pool));
+		let mut contract = TokenContractInstance{};
+		contract.constructor(10000.into());
+		// Depositing everything leaves nobody to earn the rewards
+		assert_eq!(contract.depositReward(10000.into()), false);
+		assert_eq!(contract.balanceOf(owner), 10000.into());
+		assert_eq!(contract.balanceOf(pool), 0.into());
+		assert_eq!(contract.rawStorage(reward_pool_key()), H256::zero());
+		assert_eq!(ext_get().logs().len(), 0);
+		assert_eq!(contract.depositReward(2000.into()), true);
+		assert_eq!(contract.earned(owner), 2000.into());
 	}
 
-  #[test]
-  fn should_not_transfer_to_self() {
-	let mut contract = TokenContractInstance{};
-	let owner_address = addr("ea674fdde714fd979de3edf0f56aa9716b898ec8");
-	ext_reset(|e| e.sender(owner_address.clone()));
-	let total_supply = 10000.into();
-	contract.constructor(total_supply);
-	assert_eq!(contract.balanceOf(owner_address), total_supply);
-	assert_eq!(contract.transfer(owner_address, 1000.into()), false);
-	assert_eq!(contract.transferFrom(owner_address, owner_address, 1000.into()), false);
-	assert_eq!(contract.balanceOf(owner_address), 10000.into());
-	assert_eq!(ext_get().logs().len(), 0);
-  }
+	#[test]
+	fn depositReward_should_reject_deposits_adding_nothing_or_overflowing() {
+		let owner = addr("5c2a9f3e8b1d4c7a6e0f9b2d3c4a5e6f7081920a");
+		let pool = addr("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6");
+		ext_reset(|e| e.sender(owner).address(pool));
+		let mut contract = TokenContractInstance{};
+		let supply = U256::from(10).pow(60.into());
+		contract.constructor(supply);
+		// 1 * 10^18 / (10^60 - 1) rounds down to nothing
+		assert_eq!(contract.depositReward(1.into()), false);
+		assert_eq!(contract.balanceOf(pool), 0.into());
+
+		let deposit = U256::from(10).pow(59.into());
+		assert_eq!(contract.depositReward(deposit), true);
+		// The accumulator times the supply would no longer fit in 256 bits
+		assert_eq!(contract.depositReward(deposit), false);
+		assert_eq!(contract.balanceOf(pool), deposit);
+		assert_eq!(contract.balanceOf(owner), supply - deposit);
+	}
 
 }